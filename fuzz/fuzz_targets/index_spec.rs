@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// opvm is a binary crate, so the index module is pulled in by path
+#[path = "../../src/index.rs"]
+#[allow(dead_code)]
+mod index;
+
+fuzz_target!(|data: (&str, u8)| {
+    let (spec, boundary) = data;
+    let boundary = boundary as usize;
+
+    // a whole `mc_ver[index]` spec, as typed on the command line
+    if let Some((mc_ver, raw_index)) = index::destruct_input(spec) {
+        assert!(!mc_ver.is_empty());
+        assert!(!mc_ver.contains(['[', ']']) && !raw_index.contains(['[', ']']));
+        check_index(&raw_index, boundary);
+    }
+    // or just the part between the brackets
+    check_index(spec, boundary);
+});
+
+fn check_index(raw_index: &str, boundary: usize) {
+    let (valid, _invalid) = index::parse(raw_index, boundary);
+    for i in index::purify(valid, boundary) {
+        assert!((1..=boundary).contains(&i), "index {i} out of 1~{boundary}");
+    }
+}
@@ -1,95 +1,156 @@
-use super::util::get_current_time;
+use super::util::{get_repo_dir, print_plan, record_history, record_journal};
 use crate::{
-    db::{ivec_to_string, Database, Tree},
+    db::{self, Action, Database, HistoryEvent, Origin, Store, VersionRecord},
     index::{destruct_input, parse, purify},
+    jar::{identify, version_of_file_name},
     scrape::Scraper,
 };
 use indexmap::IndexSet;
-use std::path::Path;
+use std::path::MAIN_SEPARATOR;
+use tokio::fs;
 
-pub(super) async fn handler(versions: Vec<String>) {
+pub(super) async fn handler(versions: Vec<String>, dry_run: bool) {
     if versions.is_empty() {
         // early return to avoid loading the pages
         return;
     }
-    let scrap = Scraper::new().await;
-    let db = Database::new();
-    let ver_db = db.get_version_db();
-    let conf_db = db.get_config_db();
-    let cache_db = db.get_cache_db();
+    // jars given by path or URL need no download page
+    let (sources, versions): (Vec<String>, Vec<String>) =
+        versions.into_iter().partition(|ver| is_source(ver));
+    let db = db::open();
 
-    let mut to_download = IndexSet::new();
-    for ver in versions {
-        match destruct_input(&ver) {
-            // mc ver. exists
-            Some((mc_ver, index)) if scrap.test_mc_ver(&mc_ver) => {
-                let opt_vers = scrap.get_opt_vers(&mc_ver);
-                let parsed_index = parse(&index, opt_vers.len());
-                let purified_index = purify(parsed_index.0, opt_vers.len());
-                to_download.extend(purified_index.iter().map(|&i| opt_vers[i - 1].clone()));
-                for (raw, reason) in parsed_index.1 {
-                    println!("❌ {reason} (at index '{raw}', '{mc_ver}[{raw}]')");
-                }
-            }
-            // mc ver. not exists
-            Some((mc_ver, _)) => println!("❌ No such Minecraft version '{mc_ver}'"),
-            // opt ver. exists
-            None if scrap.test_opt_ver(&ver) => {
-                to_download.insert(ver.to_string());
-            }
-            // opt ver. not exists but it is a mc ver
-            None if scrap.test_mc_ver(&ver) => {
-                let first = scrap.get_opt_vers(&ver)[0].clone();
-                to_download.insert(first);
-            }
-            // opt ver. not exists
-            None => println!("❌ No such Optifine version '{ver}'"),
-        }
+    let mut added = vec![];
+    for source in sources {
+        added.extend(add_from_source(&*db, &source, dry_run).await);
     }
+    if !versions.is_empty() {
+        added.extend(add_from_page(&db, versions, dry_run).await);
+    }
+    if !added.is_empty() {
+        record_journal(Action::Add, |journal| {
+            added.iter().for_each(|ver| journal.added(ver.clone()))
+        });
+        record_history(&*db, HistoryEvent::new(Action::Add, added));
+    }
+    db.flush().unwrap();
+}
 
-    async fn download(
-        ver_db: Tree,
-        conf_db: Tree,
-        opt_ver: String,
-        max_ver_len: usize,
-    ) -> Result<(), ()> {
-        let out_path = match conf_db.get("repo_dir").unwrap() {
-            Some(ivec) => {
-                let repo_dir = &ivec_to_string(&ivec);
-                Path::new(repo_dir).join(format!("{opt_ver}.jar"))
-            }
-            None => Path::new("repo").join(format!("{opt_ver}.jar")),
-        };
-        let result = Scraper::download_opt_file(&opt_ver, &out_path).await;
-        match result {
-            Ok(_) => {
-                let current_time = get_current_time();
-                ver_db
-                    .insert(&opt_ver, &current_time[..])
-                    .unwrap_or_else(|_| {
-                        panic!("Failed to insert Optifine version {opt_ver} into datebase")
-                    });
-                println!(
-                    "✅ {opt_ver} {} success!",
-                    ".".repeat(max_ver_len - opt_ver.len() + 3)
-                );
-                Ok(())
-            }
-            Err(_) => {
-                println!(
-                    "❌ {opt_ver} {} failed!",
-                    ".".repeat(max_ver_len - opt_ver.len() + 3)
-                );
-                Err(())
-            }
+/// A local jar or a URL, rather than a version on the download page.
+///
+/// A file named like a version (`1.16.5`) in working directory is still a version.
+fn is_source(ver: &str) -> bool {
+    ver.starts_with("http://")
+        || ver.starts_with("https://")
+        || ver.ends_with(".jar")
+        || ver.contains(['/', MAIN_SEPARATOR])
+}
+
+/// Copy a local jar or download a URL into repo, returns the version added
+async fn add_from_source(db: &dyn Store, source: &str, dry_run: bool) -> Option<String> {
+    let is_url = source.starts_with("http://") || source.starts_with("https://");
+    let file_name = source
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit(['/', '\\']).next())
+        .filter(|name| !name.is_empty());
+    let repo_dir = get_repo_dir(db);
+    if dry_run && is_url {
+        let guess = file_name.and_then(version_of_file_name);
+        print_plan(
+            &format!("{source} would be added"),
+            &[
+                "download it".to_string(),
+                match guess {
+                    Some(ver) => format!("read its version from the jar, '{ver}' by file name"),
+                    None => "read its version from the jar".to_string(),
+                },
+                format!("save to '{}'", repo_dir.join("<version>.jar").display()),
+                "insert into database".to_string(),
+            ],
+        );
+        return None;
+    }
+
+    let content = match is_url {
+        true => fetch(source).await.map_err(|err| err.to_string()),
+        false => fs::read(source).await.map_err(|err| err.to_string()),
+    };
+    let content = match content {
+        Ok(content) => content,
+        Err(err) => {
+            println!("❌ Failed to read '{source}': {err}");
+            return None;
+        }
+    };
+    let ver = match identify(&content) {
+        Ok(ver) => ver,
+        Err(reason) => {
+            println!("❌ '{source}' is {reason}");
+            return None;
+        }
+    };
+    if db.contains_version(&ver).unwrap() {
+        println!("🔵 {ver} already exists! (from '{source}')");
+        return None;
+    }
+    let path = repo_dir.join(format!("{ver}.jar"));
+    if dry_run {
+        print_plan(
+            &format!("{ver} would be added"),
+            &[
+                format!("copy from '{source}'"),
+                format!("save to '{}'", path.display()),
+                "insert into database".to_string(),
+            ],
+        );
+        return None;
+    }
+    // where it came from, as an absolute path for a local jar
+    let source_url = match is_url {
+        true => source.to_string(),
+        false => std::fs::canonicalize(source)
+            .map_or(source.to_string(), |path| path.display().to_string()),
+    };
+    let result = async {
+        fs::create_dir_all(&repo_dir).await?;
+        fs::write(&path, &content).await?;
+        let record = VersionRecord::from_file(&ver, &path, Origin::Imported)?;
+        Ok::<_, std::io::Error>(record.with_source_url(source_url))
+    };
+    match result.await {
+        Ok(record) => {
+            db.insert_version(&ver, &record).unwrap_or_else(|_| {
+                panic!("Failed to insert Optifine version {ver} into datebase")
+            });
+            println!("✅ {ver} success! (from '{source}')");
+            Some(ver)
+        }
+        Err(err) => {
+            println!("❌ {ver} failed! ({err})");
+            None
         }
     }
+}
+
+async fn fetch(url: &str) -> Result<Vec<u8>, reqwest::Error> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Download versions on the download page, returns the versions added
+async fn add_from_page(db: &Database, versions: Vec<String>, dry_run: bool) -> Vec<String> {
+    let scrap = Scraper::new().await;
+
+    let mut to_download = IndexSet::new();
+    for ver in versions {
+        to_download.extend(resolve_remote(&scrap, &ver));
+    }
 
     let max_ver_len = to_download.iter().map(|e| e.len()).max();
     let to_download: Vec<_> = to_download
         .into_iter()
         .filter_map(|ver| {
-            if ver_db.get(&ver[..]).unwrap().is_some() {
+            if db.contains_version(&ver).unwrap() {
                 println!(
                     "🔵 {ver} {} already exists!",
                     ".".repeat(max_ver_len.unwrap() - ver.len() + 3),
@@ -100,16 +161,26 @@ pub(super) async fn handler(versions: Vec<String>) {
             }
         })
         .collect();
+    if dry_run {
+        let repo_dir = get_repo_dir(&**db);
+        for ver in to_download.iter() {
+            print_plan(
+                &format!("{ver} would be added"),
+                &[
+                    format!("download from {}", Scraper::download_page_url(ver)),
+                    format!(
+                        "save to '{}'",
+                        repo_dir.join(format!("{ver}.jar")).display()
+                    ),
+                    "insert into database".to_string(),
+                ],
+            );
+        }
+        return vec![];
+    }
     let futures: Vec<_> = to_download
         .into_iter()
-        .map(|opt_ver| {
-            tokio::spawn(download(
-                ver_db.clone(),
-                conf_db.clone(),
-                opt_ver,
-                max_ver_len.unwrap(),
-            ))
-        })
+        .map(|opt_ver| tokio::spawn(download(db.clone(), opt_ver, max_ver_len.unwrap())))
         .collect();
     let results = futures::future::join_all(futures).await;
     let recap = results.iter().fold((0, 0), |(succ, fail), item| {
@@ -121,12 +192,74 @@ pub(super) async fn handler(versions: Vec<String>) {
     if recap.0 > 1 || recap.1 > 0 {
         println!("👉 {} success / {} failed", recap.0, recap.1);
     }
-    ver_db.flush_async().await.unwrap();
 
     // store opt version in db cache
-    let all_opt_vers: Vec<&String> = scrap.get_all_opt_vers().iter().collect();
-    let all_opt_vers_serial = bincode::serialize(&all_opt_vers).unwrap();
-    cache_db
-        .insert("all_opt_ver", all_opt_vers_serial)
+    let all_opt_vers: Vec<String> = scrap.get_all_opt_vers().iter().cloned().collect();
+    db.set_all_opt_vers(&all_opt_vers)
         .expect("Failed to insert Optifine version into database");
+    results.into_iter().filter_map(|r| r.ok()?.ok()).collect()
+}
+
+/// Versions on the download page that a spec of `add` means, problems are printed
+pub(super) fn resolve_remote(scrap: &Scraper, ver: &str) -> Vec<String> {
+    let mut resolved = vec![];
+    match destruct_input(ver) {
+        // mc ver. exists
+        Some((mc_ver, index)) if scrap.test_mc_ver(&mc_ver) => {
+            let opt_vers = scrap.get_opt_vers(&mc_ver);
+            let parsed_index = parse(&index, opt_vers.len());
+            let purified_index = purify(parsed_index.0, opt_vers.len());
+            resolved.extend(purified_index.iter().map(|&i| opt_vers[i - 1].clone()));
+            for (raw, reason) in parsed_index.1 {
+                println!("❌ {reason} (at index '{raw}', '{mc_ver}[{raw}]')");
+            }
+        }
+        // mc ver. not exists
+        Some((mc_ver, _)) => println!("❌ No such Minecraft version '{mc_ver}'"),
+        // opt ver. exists
+        None if scrap.test_opt_ver(ver) => {
+            resolved.push(ver.to_string());
+        }
+        // opt ver. not exists but it is a mc ver
+        None if scrap.test_mc_ver(ver) => {
+            let first = scrap.get_opt_vers(ver)[0].clone();
+            resolved.push(first);
+        }
+        // opt ver. not exists
+        None => println!("❌ No such Optifine version '{ver}'"),
+    }
+    resolved
+}
+
+/// Download a version into repo and insert its record, returns the version on success
+pub(super) async fn download(
+    db: Database,
+    opt_ver: String,
+    max_ver_len: usize,
+) -> Result<String, ()> {
+    let out_path = get_repo_dir(&*db).join(format!("{opt_ver}.jar"));
+    let result = Scraper::download_opt_file(&opt_ver, &out_path).await;
+    let record = result.and_then(|_| {
+        let record = VersionRecord::from_file(&opt_ver, &out_path, Origin::Downloaded)?;
+        Ok(record.with_source_url(Scraper::download_page_url(&opt_ver)))
+    });
+    match record {
+        Ok(record) => {
+            db.insert_version(&opt_ver, &record).unwrap_or_else(|_| {
+                panic!("Failed to insert Optifine version {opt_ver} into datebase")
+            });
+            println!(
+                "✅ {opt_ver} {} success!",
+                ".".repeat(max_ver_len - opt_ver.len() + 3)
+            );
+            Ok(opt_ver)
+        }
+        Err(_) => {
+            println!(
+                "❌ {opt_ver} {} failed!",
+                ".".repeat(max_ver_len - opt_ver.len() + 3)
+            );
+            Err(())
+        }
+    }
 }
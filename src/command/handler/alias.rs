@@ -0,0 +1,83 @@
+use super::util::{record_history, record_journal};
+use crate::db::{self, Action, HistoryEvent};
+
+pub(super) async fn set(alias: String, version: String) {
+    let db = db::open();
+    // `[` and `]` would be read as an index, e.g. `1.20.4[1]`
+    if alias.is_empty() || alias.contains(['[', ']']) {
+        println!("❌ Invalid alias '{alias}', it must be non-empty without '[' or ']'");
+        std::process::exit(1);
+    }
+    if db.contains_version(&alias).unwrap() {
+        println!("❌ '{alias}' is a local version, it cannot be an alias");
+        std::process::exit(1);
+    }
+    if !db.contains_version(&version).unwrap() {
+        println!("❌ No version '{version}' in local repo");
+        std::process::exit(1);
+    }
+    let previous = db
+        .get_alias(&alias)
+        .expect("Failed to read aliases from db");
+    if let Err(err) = db.set_alias(&alias, Some(&version)) {
+        println!("❌ Failed to write alias '{alias}': {err}");
+        std::process::exit(1);
+    }
+    match previous.as_ref() {
+        Some(previous) if *previous != version => {
+            println!("✅ {alias} -> {version} (was {previous})")
+        }
+        _ => println!("✅ {alias} -> {version}"),
+    }
+    let detail = format!("{alias} -> {version}");
+    record_journal(Action::Alias, |journal| journal.alias(alias, previous));
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Alias, vec![version]).with_detail(detail),
+    );
+    db.flush().unwrap();
+}
+
+pub(super) async fn remove(alias: String) {
+    let db = db::open();
+    let Some(version) = db
+        .get_alias(&alias)
+        .expect("Failed to read aliases from db")
+    else {
+        println!("❌ No such alias '{alias}'");
+        std::process::exit(1);
+    };
+    if let Err(err) = db.set_alias(&alias, None) {
+        println!("❌ Failed to remove alias '{alias}': {err}");
+        std::process::exit(1);
+    }
+    println!("✅ {alias} removed");
+    let detail = format!("{alias} removed");
+    record_journal(Action::Alias, |journal| {
+        journal.alias(alias, Some(version.clone()))
+    });
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Alias, vec![version]).with_detail(detail),
+    );
+    db.flush().unwrap();
+}
+
+pub(super) async fn list() {
+    let db = db::open();
+    let aliases = db.aliases().expect("Failed to read aliases from db");
+    if aliases.is_empty() {
+        println!("🔵 No alias");
+        return;
+    }
+    let max_alias_len = aliases.iter().map(|(a, _)| a.len()).max().unwrap();
+    for (alias, ver) in aliases {
+        // the version may have been removed since
+        let dangling = !db.contains_version(&ver).unwrap();
+        println!(
+            "   {alias} {} {ver}{}",
+            ".".repeat(max_alias_len - alias.len() + 3),
+            if dangling { " (not in local repo)" } else { "" }
+        );
+    }
+}
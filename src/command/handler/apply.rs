@@ -1,7 +1,7 @@
-use super::{util::sort_vers, ListSortBy};
+use super::util::{get_repo_dir, print_plan, record_history, resolve_local_one};
 use crate::{
-    db::{ivec_to_string, Database, Tree},
-    index::{destruct_input, parse, ParsedRange},
+    db::{self, Action, ConfigKey, HistoryEvent, Store},
+    journal::Journal,
 };
 use regex::Regex;
 use std::{path::Path, process::Stdio};
@@ -13,94 +13,118 @@ enum InvokeError {
     UserCancel,
     McDirNotFound,
     LauncherProfileRead,
+    LauncherProfileBackup,
 }
 
-pub(super) async fn handler(version: String) {
-    let db = Database::new();
-    let ver_db = db.get_version_db();
-    let conf_db = db.get_config_db();
-    let cache_db = db.get_cache_db();
-
-    let to_apply = match destruct_input(&version) {
-        Some((mc_ver, index)) => {
-            let pat = format!("{mc_ver}_");
-            let vers_unsorted: Vec<(String, String)> = ver_db
-                .iter()
-                .filter_map(|e| {
-                    let k = ivec_to_string(&e.unwrap().0);
-                    k.contains(&pat).then_some((k, String::new()))
-                })
-                .collect();
-            let mut vers = sort_vers(vers_unsorted, &cache_db, ListSortBy::NameNew);
-            let (parsed_valid_index, parsed_invalid_index) = parse(&index, vers.len());
-            match (parsed_valid_index.first(), parsed_invalid_index) {
-                (Some(ParsedRange::Single(i)), invalid)
-                    if !vers.is_empty() && invalid.is_empty() && parsed_valid_index.len() == 1 =>
-                {
-                    Some(vers.swap_remove(*i - 1).0)
-                }
-                _ => None,
-            }
+pub(super) async fn handler(version: String, dry_run: bool) {
+    let db = db::open();
+    let Some(ver) = resolve_local_one(&*db, &version, "applied") else {
+        return;
+    };
+    match dry_run {
+        true => print_apply_plan(&*db, &ver),
+        false => {
+            apply_version(&*db, &ver).await;
         }
-        // use the one begin first matched
-        None => {
-            let mut vers: Vec<(String, String)> = ver_db
-                .iter()
-                .filter_map(|e| {
-                    let k = ivec_to_string(&e.unwrap().0);
-                    k.contains(&version).then(|| (k, String::new()))
-                })
-                .collect();
-            (!vers.is_empty()).then(|| vers.swap_remove(0).0)
+    }
+}
+
+/// Open the installer of a local version and mark it applied, returns whether it succeeded
+pub(super) async fn apply_version(db: &dyn Store, ver: &str) -> bool {
+    let mut journal = match Journal::begin(Action::Apply) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            return false;
         }
     };
-    match to_apply {
-        Some(ver) => {
-            let result = invoke_gui_and_check(&conf_db, &ver).await;
-            if let Err(err) = result {
-                println!("🛑 {ver} failed to apply!");
-                let reason = match err {
-                    InvokeError::ExitFail => "Optifine exit abnormally",
-                    InvokeError::InvokeFail => "faild to invoke process to open GUI",
-                    InvokeError::UserCancel => "user cancel",
-                    InvokeError::McDirNotFound => "minecraft-dir has not been configured",
-                    InvokeError::LauncherProfileRead => "failed to read 'launcher_profile.json'",
-                };
-                println!("   👉 {reason}");
-            } else {
-                println!("✅ {ver} success!");
-                cache_db
-                    .insert("applied_ver", ver.as_bytes())
-                    .expect("Failed to write applied version into db");
+    if !apply_in(db, ver, &mut journal).await {
+        let _ = journal.remove();
+        return false;
+    }
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+    true
+}
+
+/// Like `apply_version`, but records the change into a journal of a bigger command
+pub(super) async fn apply_in(db: &dyn Store, ver: &str, journal: &mut Journal) -> bool {
+    let result = invoke_gui_and_check(db, ver, journal).await;
+    if let Err(err) = result {
+        println!("🛑 {ver} failed to apply!");
+        let reason = match err {
+            InvokeError::ExitFail => "Optifine exit abnormally",
+            InvokeError::InvokeFail => "faild to invoke process to open GUI",
+            InvokeError::UserCancel => "user cancel",
+            InvokeError::McDirNotFound => "minecraft-dir has not been configured",
+            InvokeError::LauncherProfileRead => "failed to read 'launcher_profile.json'",
+            InvokeError::LauncherProfileBackup => {
+                "failed to back up 'launcher_profile.json' for undo"
             }
+        };
+        println!("   👉 {reason}");
+        false
+    } else {
+        println!("✅ {ver} success!");
+        let previous = db.get_applied_ver().unwrap_or_default();
+        db.set_applied_ver(Some(ver))
+            .expect("Failed to write applied version into db");
+        journal.applied_ver(previous.clone());
+        let mut event = HistoryEvent::new(Action::Apply, vec![ver.to_string()]);
+        if let Some(previous) = previous {
+            event = event.with_detail(format!("was {previous}"));
         }
-        None => {
-            println!("❌ No version matches '{version}' in local repo");
+        record_history(db, event);
+        true
+    }
+}
+
+fn print_apply_plan(db: &dyn Store, ver: &str) {
+    let java = db
+        .get_config(ConfigKey::JavaPath)
+        .unwrap()
+        .unwrap_or("javaw".to_string());
+    let path = get_repo_dir(db).join(format!("{ver}.jar"));
+    let mut steps = vec![format!("run '{java}' -jar '{}'", path.display())];
+    match db.get_config(ConfigKey::McDir).unwrap() {
+        Some(mc_dir) => {
+            steps.push(format!(
+                "back up and update '{}'",
+                Path::new(&mc_dir).join("launcher_profiles.json").display()
+            ));
+            let previous = db.get_applied_ver().unwrap_or_default();
+            steps.push(format!(
+                "applied version: {} -> {ver}",
+                previous.as_deref().unwrap_or("none")
+            ));
         }
+        None => steps.push("fail, minecraft-dir has not been configured".to_string()),
     }
+    print_plan(&format!("{ver} would be applied"), &steps);
 }
 
-async fn invoke_gui_and_check(conf_db: &Tree, ver: &str) -> Result<(), InvokeError> {
+async fn invoke_gui_and_check(
+    db: &dyn Store,
+    ver: &str,
+    journal: &mut Journal,
+) -> Result<(), InvokeError> {
     let ver = format!("{ver}.jar");
-    let mc_dir = conf_db
-        .get("mc_dir")
+    let mc_dir = db
+        .get_config(ConfigKey::McDir)
         .unwrap()
-        .map(|ivec| ivec_to_string(&ivec))
         .ok_or(InvokeError::McDirNotFound)?;
     let launcher_profile_path = Path::new(&mc_dir).join("launcher_profiles.json");
-    let java = conf_db
-        .get("java_path")
+    let java = db
+        .get_config(ConfigKey::JavaPath)
         .unwrap()
-        .map(|ivec| ivec_to_string(&ivec))
         .unwrap_or("javaw".to_string());
-    let repo = conf_db
-        .get("repo_dir")
-        .unwrap()
-        .map(|ivec| ivec_to_string(&ivec))
-        .unwrap_or("repo".to_string());
-    let path = Path::new(&repo).join(ver);
+    let path = get_repo_dir(db).join(ver);
 
     let profile_before = get_launcher_profile(&launcher_profile_path).await?;
+    journal
+        .backup_launcher_profile(&launcher_profile_path)
+        .map_err(|_| InvokeError::LauncherProfileBackup)?;
     let mut child = Command::new(java)
         .arg("-jar")
         .arg(path)
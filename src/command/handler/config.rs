@@ -1,6 +1,13 @@
-use crate::db::{self, ivec_to_string, Database, Tree};
+use super::{
+    prompt,
+    util::{record_history, record_journal},
+};
+use crate::{
+    db::{self, Action, ConfigKey, Database, HistoryEvent, Store},
+    home,
+};
 use std::{
-    io::Write,
+    collections::BTreeMap,
     path::Path,
     process::{ExitStatus, Stdio},
 };
@@ -16,10 +23,15 @@ pub(super) async fn handler(
     minecraft_dir: Option<String>,
     java_path: Option<String>,
     repo_dir: Option<String>,
+    trash_days: Option<String>,
     test: bool,
 ) {
-    let scaffolding = minecraft_dir.is_none() && java_path.is_none() && repo_dir.is_none();
-    let db = Database::new().get_config_db();
+    let scaffolding = minecraft_dir.is_none()
+        && java_path.is_none()
+        && repo_dir.is_none()
+        && trash_days.is_none();
+    let db = db::open();
+    let config_before = db.all_config().expect("Failed to read config from db");
 
     let mut tasks = vec![];
 
@@ -62,13 +74,20 @@ pub(super) async fn handler(
             }
         }));
     }
+    if let Some(days) = trash_days {
+        match config_trash_days(&*db, &days) {
+            Ok(_) if days.is_empty() => println!("✅ trash-days unset"),
+            Ok(_) => println!("✅ trash-days set to '{days}'"),
+            Err(reason) => println!("❌ {reason}"),
+        }
+    }
     if scaffolding {
         if test {
             let db = db.clone();
 
-            async fn judge<F, Fut>(db: Tree, f: F)
+            async fn judge<F, Fut>(db: Database, f: F)
             where
-                F: Fn(Tree) -> Fut,
+                F: Fn(Database) -> Fut,
                 Fut: std::future::Future<Output = Result<String, String>>,
             {
                 match f(db.clone()).await {
@@ -85,19 +104,25 @@ pub(super) async fn handler(
 
             tasks.push(tokio::spawn(async move {
                 println!("⭐ opvm config scaffolding ⭐");
-                let input = read_line("👉 --minecraft-dir: ");
+                let Some(input) = prompt::input("👉 --minecraft-dir: ") else {
+                    return;
+                };
                 if config_mc_dir(db.clone(), &input).is_ok() {
                     println!("✅ set to '{input}'");
                 } else {
                     println!("❌ Failed to config minecraft-dir")
                 }
-                let input = read_line("👉 --java-path: ");
+                let Some(input) = prompt::input("👉 --java-path: ") else {
+                    return;
+                };
                 if config_java(db.clone(), &input).is_ok() {
                     println!("✅ set to '{input}'");
                 } else {
                     println!("❌ Failed to config java-path")
                 }
-                let input = read_line("👉 --repo-dir: ");
+                let Some(input) = prompt::input("👉 --repo-dir: ") else {
+                    return;
+                };
                 if config_repo(db.clone(), &input).is_ok() {
                     println!("✅ set to '{input}'");
                 } else {
@@ -107,34 +132,56 @@ pub(super) async fn handler(
         }
     }
     let _results = futures::future::join_all(tasks).await;
-    db.flush_async().await.expect("Database flush failed");
+    journal_config(&*db, config_before);
+    db.flush().expect("Database flush failed");
 }
 
-fn read_line(print: &str) -> String {
-    let mut input = String::new();
-    print!("{print}");
-    std::io::stdout().flush().unwrap();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+/// Keep the entries changed since `before` in the undo journal
+fn journal_config(db: &dyn Store, before: BTreeMap<ConfigKey, String>) {
+    let Ok(after) = db.all_config() else {
+        return;
+    };
+    let changed: Vec<_> = ConfigKey::ALL
+        .into_iter()
+        .filter(|key| before.get(key) != after.get(key))
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+    record_journal(Action::Config, |journal| {
+        for key in changed {
+            journal.config(key, before.get(&key).cloned());
+        }
+    });
 }
 
-fn config_mc_dir(db: Tree, mc_dir: &String) -> db::Result<()> {
-    if mc_dir.is_empty() {
-        db.remove("mc_dir")?;
-    } else {
-        db.insert("mc_dir", mc_dir.as_bytes())?;
-    }
+/// Set a config entry, an empty value unsets it
+fn set_config(db: &dyn Store, key: ConfigKey, value: &str) -> db::Result<()> {
+    let value = Some(value).filter(|v| !v.is_empty());
+    db.set_config(key, value)?;
+    let detail = match value {
+        Some(value) => format!("{} = '{value}'", key.option()),
+        None => format!("{} unset", key.option()),
+    };
+    record_history(
+        db,
+        HistoryEvent::new(Action::Config, vec![]).with_detail(detail),
+    );
     Ok(())
 }
 
-async fn test_mc_dir(db: Tree) -> Result<String, String> {
-    let entry = db.get("mc_dir").unwrap();
+fn config_mc_dir(db: Database, mc_dir: &str) -> db::Result<()> {
+    set_config(&*db, ConfigKey::McDir, mc_dir)
+}
+
+async fn test_mc_dir(db: Database) -> Result<String, String> {
+    let entry = db.get_config(ConfigKey::McDir).unwrap();
     // 1. test if entry exists
     if entry.is_none() {
         return Err("🛑 minecraft-dir has not been configured yet".to_string());
     }
     // 2. test if entry is a valid dir
-    let path_string = ivec_to_string(entry.as_ref().unwrap());
+    let path_string = entry.unwrap();
     let path = Path::new(&path_string);
     if !is_readable_dir(path).await {
         return Err(format!(
@@ -167,24 +214,19 @@ async fn test_mc_dir(db: Tree) -> Result<String, String> {
     Ok(format!("✅ minecraft-dir: '{path_string}'"))
 }
 
-fn config_java(db: Tree, java_path: &String) -> db::Result<()> {
-    if java_path.is_empty() {
-        db.remove("java_path")?;
-    } else {
-        db.insert("java_path", java_path.as_bytes())?;
-    }
-    Ok(())
+fn config_java(db: Database, java_path: &str) -> db::Result<()> {
+    set_config(&*db, ConfigKey::JavaPath, java_path)
 }
 
-async fn test_java(db: Tree) -> Result<String, String> {
-    let entry = db.get("java_path").unwrap();
+async fn test_java(db: Database) -> Result<String, String> {
+    let entry = db.get_config(ConfigKey::JavaPath).unwrap();
 
-    create_test_jar_and_dir_if_not_exist()
-        .await
-        .map_err(|_| "Failed to create .test_resources/Test.jar".to_string())?;
+    create_test_jar_and_dir_if_not_exist().await.map_err(|_| {
+        let test_jar = home::test_resources_dir().join("Test.jar");
+        format!("Failed to create {}", test_jar.display())
+    })?;
 
     if let Some(java) = entry {
-        let java = ivec_to_string(&java);
         let status = open_process_to_test_java(&java).await;
         match status {
             Ok(Ok(exit)) if exit.success() => Ok(format!("✅ java-path: '{java}'")),
@@ -200,8 +242,9 @@ async fn test_java(db: Tree) -> Result<String, String> {
 }
 
 async fn create_test_jar_and_dir_if_not_exist() -> Result<(), std::io::Error> {
-    let filepath = Path::new(".test_resources/Test.jar");
-    fs::create_dir_all(".test_resources").await?;
+    let test_resources = home::test_resources_dir();
+    let filepath = test_resources.join("Test.jar");
+    fs::create_dir_all(&test_resources).await?;
     if !filepath.exists() {
         fs::write(filepath, TEST_JAR).await?;
     }
@@ -211,7 +254,7 @@ async fn create_test_jar_and_dir_if_not_exist() -> Result<(), std::io::Error> {
 async fn open_process_to_test_java(java: &str) -> Result<Result<ExitStatus, ()>, std::io::Error> {
     let mut child = Command::new(java)
         .arg("-jar")
-        .arg(".test_resources/Test.jar")
+        .arg(home::test_resources_dir().join("Test.jar"))
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()?;
@@ -234,20 +277,24 @@ async fn open_process_to_test_java(java: &str) -> Result<Result<ExitStatus, ()>,
     Ok(Ok(status))
 }
 
-fn config_repo(db: Tree, repo_dir: &String) -> db::Result<()> {
-    if repo_dir.is_empty() {
-        db.remove("repo_dir")?;
-    } else {
-        db.insert("repo_dir", repo_dir.as_bytes())?;
+fn config_trash_days(db: &dyn Store, days: &str) -> Result<(), String> {
+    if !days.is_empty() && days.parse::<u32>().is_err() {
+        return Err(format!(
+            "Invalid trash-days '{days}', it must be a number of days"
+        ));
     }
-    Ok(())
+    set_config(db, ConfigKey::TrashDays, days)
+        .map_err(|_| "Failed to config trash-days".to_string())
+}
+
+fn config_repo(db: Database, repo_dir: &str) -> db::Result<()> {
+    set_config(&*db, ConfigKey::RepoDir, repo_dir)
 }
 
-async fn test_repo(db: Tree) -> Result<String, String> {
-    let entry = db.get("repo_dir").unwrap();
+async fn test_repo(db: Database) -> Result<String, String> {
+    let entry = db.get_config(ConfigKey::RepoDir).unwrap();
 
-    if let Some(repo) = entry {
-        let repo_string = ivec_to_string(&repo);
+    if let Some(repo_string) = entry {
         let repo = Path::new(&repo_string);
 
         if is_readable_dir(repo).await && is_writable_dir(repo).await {
@@ -257,12 +304,12 @@ async fn test_repo(db: Tree) -> Result<String, String> {
         }
     } else {
         // automaticall create nessasary parent directory
-        let repo_dir = Path::new("repo");
-        tokio::fs::create_dir_all(repo_dir)
+        let repo_dir = home::default_repo_dir();
+        tokio::fs::create_dir_all(&repo_dir)
             .await
             .map_err(|_| "❌ Failed to create default repo directory")?;
-        if is_writable_dir(repo_dir).await {
-            Ok("✅ repo-dir: default (repo/)".to_string())
+        if is_writable_dir(&repo_dir).await {
+            Ok(format!("✅ repo-dir: default ({})", repo_dir.display()))
         } else {
             Err("🛑 Cannot add/remove file at default local repo".to_string())
         }
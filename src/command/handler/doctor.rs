@@ -0,0 +1,262 @@
+use super::util::{get_repo_dir, record_history, warn_dangling_aliases};
+use crate::{
+    db::{self, Action, ConfigKey, HistoryEvent, Origin, Store, VersionRecord},
+    journal::Journal,
+    trash,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::{fs, io::AsyncReadExt};
+
+enum Problem {
+    /// A jar in repo dir without entry in database
+    OrphanJar(String),
+    /// An entry in database without jar in repo dir
+    MissingJar(String),
+    /// A jar that is empty or not a zip file
+    BrokenJar(String, &'static str),
+    /// The applied version is no longer in database
+    StaleApplied(String),
+    /// A configured path that cannot be reached
+    BadConfig(ConfigKey, String),
+    /// The repo dir does not exist
+    MissingRepo(PathBuf),
+}
+
+impl Problem {
+    fn describe(&self) -> String {
+        match self {
+            Problem::OrphanJar(name) => format!("'{name}.jar' is in repo but not in database"),
+            Problem::MissingJar(name) => format!("'{name}' is in database but its jar is missing"),
+            Problem::BrokenJar(name, reason) => format!("'{name}.jar' is {reason}"),
+            Problem::StaleApplied(name) => {
+                format!("applied version '{name}' is not in local repo")
+            }
+            Problem::BadConfig(key, value) => {
+                format!("{} '{value}' cannot be reached", key.option())
+            }
+            Problem::MissingRepo(path) => format!("repo-dir '{}' does not exist", path.display()),
+        }
+    }
+}
+
+pub(super) async fn handler(fix: bool) {
+    let db = db::open();
+    let repo_dir = get_repo_dir(&*db);
+
+    let problems = diagnose(&*db, &repo_dir).await;
+    if problems.is_empty() {
+        println!("✅ No problem found");
+        return;
+    }
+    let mut journal = match fix {
+        true => match Journal::begin(Action::Doctor) {
+            Ok(journal) => Some(journal),
+            Err(err) => {
+                println!("❌ Failed to create undo journal: {err}");
+                return;
+            }
+        },
+        false => None,
+    };
+    let mut fixed = 0;
+    for problem in problems.iter() {
+        println!("🛑 {}", problem.describe());
+        let Some(journal) = journal.as_mut() else {
+            continue;
+        };
+        match repair(problem, &*db, &repo_dir, journal).await {
+            Ok(action) => {
+                println!("   ✅ {action}");
+                fixed += 1;
+            }
+            Err(reason) => println!("   ❌ {reason}"),
+        }
+    }
+    if let Some(journal) = journal {
+        println!("👉 {} problems found / {fixed} fixed", problems.len());
+        let entry = journal.entry();
+        let removed: Vec<String> = entry.removed.iter().map(|(k, _)| k.clone()).collect();
+        let versions = removed.iter().chain(entry.loaded.iter()).cloned().collect();
+        if let Err(err) = journal.commit() {
+            println!("❌ Failed to write undo journal: {err}");
+        }
+        warn_dangling_aliases(&*db, &removed);
+        if fixed > 0 {
+            record_history(
+                &*db,
+                HistoryEvent::new(Action::Doctor, versions)
+                    .with_detail(format!("{fixed} problems fixed")),
+            );
+        }
+        db.flush().unwrap();
+    } else {
+        println!(
+            "👉 {} problems found, run `opvm doctor --fix` to repair",
+            problems.len()
+        );
+    }
+}
+
+async fn diagnose(db: &dyn Store, repo_dir: &Path) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    // configured paths
+    if let Some(mc_dir) = db.get_config(ConfigKey::McDir).unwrap() {
+        if !Path::new(&mc_dir).is_dir() {
+            problems.push(Problem::BadConfig(ConfigKey::McDir, mc_dir));
+        }
+    }
+    if let Some(java) = db.get_config(ConfigKey::JavaPath).unwrap() {
+        if find_executable(&java).is_none() {
+            problems.push(Problem::BadConfig(ConfigKey::JavaPath, java));
+        }
+    }
+    if !repo_dir.is_dir() {
+        // nothing in repo can be checked further
+        problems.push(Problem::MissingRepo(repo_dir.to_path_buf()));
+        return problems;
+    }
+
+    // repo against database
+    let jars = get_repo_jars(repo_dir).await;
+    let entries: HashSet<String> = db.versions().unwrap().into_iter().map(|(k, _)| k).collect();
+    let mut orphans: Vec<_> = jars.difference(&entries).collect();
+    orphans.sort();
+    problems.extend(
+        orphans
+            .into_iter()
+            .map(|name| Problem::OrphanJar(name.clone())),
+    );
+    let mut missing: Vec<_> = entries.difference(&jars).collect();
+    missing.sort();
+    problems.extend(
+        missing
+            .into_iter()
+            .map(|name| Problem::MissingJar(name.clone())),
+    );
+    let mut jars: Vec<_> = jars.into_iter().collect();
+    jars.sort();
+    for name in jars {
+        if let Some(reason) = check_jar(&repo_dir.join(format!("{name}.jar"))).await {
+            problems.push(Problem::BrokenJar(name, reason));
+        }
+    }
+
+    // applied version
+    if let Some(applied) = db.get_applied_ver().unwrap() {
+        if !entries.contains(&applied) {
+            problems.push(Problem::StaleApplied(applied));
+        }
+    }
+    problems
+}
+
+async fn repair(
+    problem: &Problem,
+    db: &dyn Store,
+    repo_dir: &Path,
+    journal: &mut Journal,
+) -> Result<String, String> {
+    let db_err = |_| "failed to write database".to_string();
+    match problem {
+        Problem::OrphanJar(name) => {
+            let path = repo_dir.join(format!("{name}.jar"));
+            let record = VersionRecord::from_file(name, &path, Origin::Loaded)
+                .map_err(|_| format!("failed to read '{}'", path.display()))?;
+            db.insert_version(name, &record).map_err(db_err)?;
+            journal.loaded(name.clone());
+            Ok("added into database".to_string())
+        }
+        Problem::MissingJar(name) => {
+            if let Some(record) = db.remove_version(name).map_err(db_err)? {
+                journal.removed(name.clone(), record);
+            }
+            Ok("removed from database".to_string())
+        }
+        Problem::BrokenJar(name, _) => {
+            let path = repo_dir.join(format!("{name}.jar"));
+            // a jar without entry is kept with what can be known of it
+            let record = match db.get_version(name).map_err(db_err)? {
+                Some(record) => record,
+                None => VersionRecord::from_file(name, &path, Origin::Loaded)
+                    .map_err(|_| format!("failed to read '{}'", path.display()))?,
+            };
+            // moved into trash, so undo and `opvm trash restore` can bring it back
+            let id = trash::put(name, &path, &record)
+                .map_err(|_| format!("failed to move '{}' into trash", path.display()))?;
+            db.remove_version(name).map_err(db_err)?;
+            journal.removed(name.clone(), record);
+            journal.trashed(name.clone(), id);
+            Ok("moved into trash and removed from database".to_string())
+        }
+        Problem::StaleApplied(name) => {
+            db.set_applied_ver(None).map_err(db_err)?;
+            journal.applied_ver(Some(name.clone()));
+            Ok("cleared applied version".to_string())
+        }
+        Problem::BadConfig(key, value) => {
+            db.set_config(*key, None).map_err(db_err)?;
+            journal.config(*key, Some(value.clone()));
+            Ok(format!(
+                "unset {}, configure it again with `opvm config`",
+                key.option()
+            ))
+        }
+        Problem::MissingRepo(path) => {
+            fs::create_dir_all(path)
+                .await
+                .map_err(|_| format!("failed to create '{}'", path.display()))?;
+            Ok("created".to_string())
+        }
+    }
+}
+
+async fn get_repo_jars(repo_dir: &Path) -> HashSet<String> {
+    let mut jars = HashSet::new();
+    let Ok(mut readdir) = fs::read_dir(repo_dir).await else {
+        return jars;
+    };
+    while let Ok(Some(entry)) = readdir.next_entry().await {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) != Some("jar") {
+            continue;
+        }
+        if let Some(filename) = path.file_stem() {
+            jars.insert(filename.to_string_lossy().to_string());
+        }
+    }
+    jars
+}
+
+/// Why the jar is broken, if it is
+async fn check_jar(path: &Path) -> Option<&'static str> {
+    let Ok(mut file) = fs::File::open(path).await else {
+        return Some("not readable");
+    };
+    let mut magic = [0; 4];
+    match file.read(&mut magic).await {
+        Ok(0) => Some("zero-byte"),
+        // a local file header, or the end of an empty archive
+        Ok(4) if magic == *b"PK\x03\x04" || magic == *b"PK\x05\x06" => None,
+        Ok(_) => Some("not a zip file"),
+        Err(_) => Some("not readable"),
+    }
+}
+
+/// A path to an existing file, or a command name that can be found in `PATH`
+fn find_executable(java: &str) -> Option<PathBuf> {
+    let path = Path::new(java);
+    if path.components().count() > 1 || path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        [java.to_string(), format!("{java}.exe")]
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
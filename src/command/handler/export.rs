@@ -0,0 +1,38 @@
+use crate::db::{self, StateDocument};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+use tokio::fs;
+
+pub(super) async fn handler(file: Option<PathBuf>) {
+    let db = db::open();
+    let document = match StateDocument::export(&*db) {
+        Ok(document) => document,
+        Err(err) => {
+            println!("❌ Failed to read database: {err}");
+            return;
+        }
+    };
+    let json = serde_json::to_string_pretty(&document).expect("Failed to serialize state");
+    match file {
+        Some(file) => match fs::write(&file, json).await {
+            Ok(_) => println!(
+                "✅ Exported {} versions to '{}'",
+                document.versions.len(),
+                file.display()
+            ),
+            Err(err) => println!("❌ Failed to write '{}': {err}", file.display()),
+        },
+        // print the document only, so it can be piped
+        None => {
+            let mut stdout = io::stdout().lock();
+            match writeln!(stdout, "{json}").and_then(|_| stdout.flush()) {
+                Ok(_) => {}
+                // the reader stopped early, e.g. `opvm export | head`
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+                Err(err) => eprintln!("❌ Failed to write the document: {err}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use super::{
+    remove::handle_one_file,
+    util::{
+        format_size, get_repo_dir, print_plan, record_history, sort_vers, warn_dangling_aliases,
+        UNITS,
+    },
+    ListSortBy,
+};
+use crate::{
+    db::{self, Action, HistoryEvent, VersionRecord},
+    journal::Journal,
+    version::{MinecraftVersion, OptifineVersion},
+};
+use chrono::{Duration, Utc};
+use indexmap::IndexMap;
+use std::{collections::HashMap, sync::Arc};
+
+/// The retention policies of one run, versions matching any of them are removed
+pub(super) struct Policy {
+    /// Keep this many newest versions of each Minecraft version
+    pub keep: Option<usize>,
+    /// Drop previews older than a local stable version of the same Minecraft version
+    pub drop_previews: bool,
+    /// Drop versions added more than this many days ago
+    pub max_age: Option<u32>,
+    /// Drop the earliest added versions until the repo is at most this many bytes
+    pub max_size: Option<u64>,
+}
+
+pub(super) async fn handler(policy: Policy, force: bool, dry_run: bool) {
+    if policy.keep.is_none()
+        && !policy.drop_previews
+        && policy.max_age.is_none()
+        && policy.max_size.is_none()
+    {
+        println!("❌ No policy given, use --keep, --drop-previews, --max-age or --max-size");
+        return;
+    }
+    let db = db::open();
+    let repo_dir = get_repo_dir(&*db);
+    // newest first, as index 1 of `apply` and `remove`
+    let vers = sort_vers(
+        db.versions().expect("Failed to read versions from db"),
+        &*db,
+        ListSortBy::NameNew,
+    );
+    let applied_ver = db.get_applied_ver().unwrap_or_default();
+    // the jar as it is now, the record may be older than that
+    let sizes: HashMap<&String, u64> = vers
+        .iter()
+        .map(|(ver, record)| {
+            let path = repo_dir.join(format!("{ver}.jar"));
+            let size = std::fs::metadata(path).map(|meta| meta.len()).ok();
+            (ver, size.or(record.size).unwrap_or_default())
+        })
+        .collect();
+
+    let mut doomed = Doomed {
+        applied_ver,
+        force,
+        versions: IndexMap::new(),
+        kept: vec![],
+    };
+    doomed.select(&policy, &vers, &sizes);
+
+    let doomed = doomed.versions;
+    if doomed.is_empty() {
+        println!("🔵 Nothing to collect");
+        return;
+    }
+    // in the order of `list`
+    let doomed: Vec<(String, String)> = vers
+        .iter()
+        .filter_map(|(ver, _)| Some((ver.clone(), doomed.get(ver)?.clone())))
+        .collect();
+    if dry_run {
+        for (ver, reason) in doomed.iter() {
+            print_plan(
+                &format!("{ver} would be removed ({reason})"),
+                &[
+                    format!(
+                        "move '{}' into trash",
+                        repo_dir.join(format!("{ver}.jar")).display()
+                    ),
+                    "remove from database".to_string(),
+                ],
+            );
+        }
+        let reclaimed = doomed.iter().map(|(ver, _)| sizes[ver]).sum();
+        println!(
+            "👉 {} versions, {} would be reclaimed",
+            doomed.len(),
+            format_size(reclaimed)
+        );
+        return;
+    }
+
+    // jars are moved into trash, so `opvm undo` can bring them back
+    let mut journal = match Journal::begin(Action::Gc) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            return;
+        }
+    };
+    let base = Arc::new(repo_dir.clone());
+    let mut removed = vec![];
+    let mut reclaimed = 0;
+    for (ver, reason) in doomed {
+        match handle_one_file(db.clone(), base.clone(), Arc::new(ver.clone())).await {
+            Ok((record, id)) => {
+                println!("✅ {ver} removed ({reason})");
+                reclaimed += sizes[&ver];
+                journal.removed(ver.clone(), record);
+                journal.trashed(ver.clone(), id);
+                removed.push(ver);
+            }
+            Err(reason) => println!("❌ {ver} failed to remove: {reason}"),
+        }
+    }
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+    warn_dangling_aliases(&*db, &removed);
+    if removed.is_empty() {
+        return;
+    }
+    let detail = format!("{} reclaimed", format_size(reclaimed));
+    println!("👉 {} versions removed, {detail}", removed.len());
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Gc, removed).with_detail(detail),
+    );
+    db.flush().unwrap();
+}
+
+/// Versions to remove with the reason, and the ones kept against a policy
+struct Doomed {
+    applied_ver: Option<String>,
+    force: bool,
+    versions: IndexMap<String, String>,
+    kept: Vec<String>,
+}
+
+impl Doomed {
+    /// Doom what the policies match, `vers` are sorted newest first
+    fn select(
+        &mut self,
+        policy: &Policy,
+        vers: &[(String, VersionRecord)],
+        sizes: &HashMap<&String, u64>,
+    ) {
+        if let Some(keep) = policy.keep {
+            let mut seen: HashMap<MinecraftVersion, usize> = HashMap::new();
+            for (ver, record) in vers.iter() {
+                // versions of no known Minecraft version are left alone
+                let Some(mc_ver) = MinecraftVersion::of_opt_ver(ver) else {
+                    continue;
+                };
+                let count = seen.entry(mc_ver.clone()).or_default();
+                *count += 1;
+                if *count > keep {
+                    let reason = format!("more than {keep} versions of {mc_ver}");
+                    self.doom(ver, record, reason);
+                }
+            }
+        }
+        if policy.drop_previews {
+            let parsed: Vec<(&String, &VersionRecord, OptifineVersion)> = vers
+                .iter()
+                .filter_map(|(ver, record)| Some((ver, record, ver.parse().ok()?)))
+                .collect();
+            for (ver, record, opt_ver) in parsed.iter().filter(|(_, _, v)| v.is_preview()) {
+                let stable = parsed.iter().find(|(other, _, v)| {
+                    !v.is_preview()
+                        && v > opt_ver
+                        && MinecraftVersion::of_opt_ver(other) == MinecraftVersion::of_opt_ver(ver)
+                });
+                if let Some((stable, _, _)) = stable {
+                    self.doom(ver, record, format!("superseded by {stable}"));
+                }
+            }
+        }
+        if let Some(max_age) = policy.max_age {
+            let deadline = Utc::now() - Duration::days(max_age.into());
+            for (ver, record) in vers.iter().filter(|(_, r)| r.added_at < deadline) {
+                self.doom(ver, record, format!("added more than {max_age} days ago"));
+            }
+        }
+        if let Some(max_size) = policy.max_size {
+            let mut total: u64 = vers
+                .iter()
+                .filter(|(ver, _)| !self.versions.contains_key(ver))
+                .map(|(ver, _)| sizes[ver])
+                .sum();
+            // the earliest added go first
+            let mut by_added: Vec<&(String, VersionRecord)> = vers.iter().collect();
+            by_added.sort_by_key(|(_, record)| record.added_at);
+            for (ver, record) in by_added {
+                if total <= max_size {
+                    break;
+                }
+                if self.doom(ver, record, format!("repo over {}", format_size(max_size))) {
+                    total -= sizes[ver];
+                }
+            }
+            if total > max_size {
+                println!(
+                    "🔵 Repo stays over {}, what is left is applied or pinned",
+                    format_size(max_size)
+                );
+            }
+        }
+    }
+
+    /// Add a version unless it is applied or pinned, returns whether it is added
+    fn doom(&mut self, ver: &String, record: &VersionRecord, reason: String) -> bool {
+        if self.versions.contains_key(ver) || self.kept.contains(ver) {
+            return false;
+        }
+        if self.applied_ver.as_ref() == Some(ver) {
+            println!("🔵 {ver} is applied, keep it");
+            self.kept.push(ver.clone());
+            return false;
+        }
+        if record.pinned && !self.force {
+            println!("📌 {ver} is pinned, keep it (use --force to remove it)");
+            self.kept.push(ver.clone());
+            return false;
+        }
+        self.versions.insert(ver.clone(), reason);
+        true
+    }
+}
+
+/// Parse the count of `--keep`, which must leave something
+pub(in crate::command) fn parse_keep(keep: &str) -> Result<usize, String> {
+    match keep.parse::<usize>() {
+        Ok(0) => Err("0 would remove every version, use `opvm remove --all` for that".to_string()),
+        Ok(keep) => Ok(keep),
+        Err(_) => Err(format!("invalid count '{keep}'")),
+    }
+}
+
+/// Parse a size like `500M`, `1G`, `1.5GiB` or `1024`, in bytes
+pub(in crate::command) fn parse_size(size: &str) -> Result<u64, String> {
+    let upper = size.trim().to_uppercase();
+    let number = upper.trim_end_matches("IB").trim_end_matches('B');
+    let (number, unit) = UNITS
+        .iter()
+        .find_map(|(suffix, unit)| Some((number.strip_suffix(suffix)?, *unit)))
+        .unwrap_or((number, 1));
+    match number.trim().parse::<f64>() {
+        Ok(n) if n >= 0.0 => Ok((n * unit as f64) as u64),
+        _ => Err(format!("invalid size '{size}', try e.g. 500M or 1G")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Versions sorted newest first, each added some days ago
+    fn vers(vers: &[(&str, i64)]) -> Vec<(String, VersionRecord)> {
+        vers.iter()
+            .map(|(ver, days)| {
+                let record = VersionRecord::at(Utc::now() - Duration::days(*days));
+                (ver.to_string(), record)
+            })
+            .collect()
+    }
+
+    fn policy() -> Policy {
+        Policy {
+            keep: None,
+            drop_previews: false,
+            max_age: None,
+            max_size: None,
+        }
+    }
+
+    /// The doomed versions in the order doomed, each jar is 100 bytes
+    fn select(
+        policy: Policy,
+        vers: &[(String, VersionRecord)],
+        applied: Option<&str>,
+    ) -> Vec<String> {
+        let sizes = vers.iter().map(|(ver, _)| (ver, 100)).collect();
+        let mut doomed = Doomed {
+            applied_ver: applied.map(str::to_string),
+            force: false,
+            versions: IndexMap::new(),
+            kept: vec![],
+        };
+        doomed.select(&policy, vers, &sizes);
+        doomed.versions.into_keys().collect()
+    }
+
+    #[test]
+    fn keep_newest_of_each_minecraft_version() {
+        let mut vers = vers(&[
+            ("1.20.4_HD_U_I7", 0),
+            ("1.20.4_HD_U_I6", 0),
+            ("1.20.4_HD_U_I5", 0),
+            ("1.16.5_HD_U_G8", 0),
+            ("1.16.5_HD_U_G7", 0),
+            ("custom", 0),
+        ]);
+        let keep = || Policy {
+            keep: Some(1),
+            ..policy()
+        };
+        assert_eq!(
+            select(keep(), &vers, None),
+            ["1.20.4_HD_U_I6", "1.20.4_HD_U_I5", "1.16.5_HD_U_G7"]
+        );
+        // applied and pinned ones are kept
+        vers[4].1.pinned = true;
+        assert_eq!(
+            select(keep(), &vers, Some("1.20.4_HD_U_I5")),
+            ["1.20.4_HD_U_I6"]
+        );
+    }
+
+    #[test]
+    fn drop_previews_superseded_by_stable() {
+        let vers = vers(&[
+            ("1.20.4_HD_U_I8_pre1", 0),
+            ("1.20.4_HD_U_I7", 0),
+            ("1.20.4_HD_U_I7_pre3", 0),
+            ("1.16.5_HD_U_H1_pre2", 0),
+            ("1.16.5_HD_U_G8", 0),
+        ]);
+        let policy = Policy {
+            drop_previews: true,
+            ..policy()
+        };
+        assert_eq!(select(policy, &vers, None), ["1.20.4_HD_U_I7_pre3"]);
+    }
+
+    #[test]
+    fn max_age_drops_versions_added_long_ago() {
+        let vers = vers(&[
+            ("1.20.4_HD_U_I7", 200),
+            ("1.20.4_HD_U_I6", 10),
+            ("1.16.5_HD_U_G8", 100),
+        ]);
+        let policy = Policy {
+            max_age: Some(90),
+            ..policy()
+        };
+        assert_eq!(
+            select(policy, &vers, None),
+            ["1.20.4_HD_U_I7", "1.16.5_HD_U_G8"]
+        );
+    }
+
+    #[test]
+    fn max_size_drops_earliest_added_first() {
+        let vers = vers(&[
+            ("1.20.4_HD_U_I7", 100),
+            ("1.20.4_HD_U_I6", 1),
+            ("1.16.5_HD_U_G8", 10),
+            ("1.16.5_HD_U_G7", 5),
+        ]);
+        let policy = |max_size| Policy {
+            max_size: Some(max_size),
+            ..policy()
+        };
+        assert_eq!(
+            select(policy(250), &vers, None),
+            ["1.20.4_HD_U_I7", "1.16.5_HD_U_G8"]
+        );
+        assert!(select(policy(400), &vers, None).is_empty());
+        // versions doomed by other policies count as gone already
+        let both = Policy {
+            keep: Some(1),
+            ..policy(150)
+        };
+        assert_eq!(
+            select(both, &vers, None),
+            ["1.20.4_HD_U_I6", "1.16.5_HD_U_G7", "1.20.4_HD_U_I7"]
+        );
+    }
+
+    #[test]
+    fn keep_must_leave_something() {
+        assert!(parse_keep("0").is_err());
+        assert!(parse_keep("x").is_err());
+        assert_eq!(parse_keep("3"), Ok(3));
+    }
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("1.5GiB"), Ok(3 << 29));
+        assert!(parse_size("-1G").is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::db::{self, Action, HistoryEvent};
+use chrono::{Local, NaiveDate};
+
+pub(super) async fn handler(
+    action: Option<Action>,
+    version: Option<String>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    limit: Option<usize>,
+) {
+    let db = db::open();
+    let history = db.history().expect("Failed to read history from db");
+    let matches = |event: &HistoryEvent| {
+        let date = event.at.with_timezone(&Local).date_naive();
+        action.is_none_or(|action| event.action == action)
+            && version
+                .as_deref()
+                .is_none_or(|pat| event.versions.iter().any(|v| v.contains(pat)))
+            && since.is_none_or(|since| date >= since)
+            && until.is_none_or(|until| date <= until)
+    };
+    let events: Vec<HistoryEvent> = history.into_iter().filter(matches).collect();
+    if events.is_empty() {
+        println!("🔵 No history found");
+        return;
+    }
+    // the newest ones are the last ones, like a shell history
+    let skip = limit.map_or(0, |limit| events.len().saturating_sub(limit));
+    let events = &events[skip..];
+    let width = events
+        .iter()
+        .map(|event| event.action.to_string().len())
+        .max()
+        .unwrap_or_default();
+    for event in events {
+        let mut line = format!("{} {:<width$}", event.at_local(), event.action.to_string());
+        if !event.versions.is_empty() {
+            line.push(' ');
+            line.push_str(&event.versions.join(", "));
+        }
+        if let Some(detail) = event.detail.as_ref() {
+            line.push_str(&format!(" ({detail})"));
+        }
+        println!("{line}");
+    }
+}
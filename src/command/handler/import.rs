@@ -0,0 +1,73 @@
+use super::util::record_history;
+use crate::{
+    db::{self, Action, HistoryEvent, StateDocument, FORMAT_VERSION},
+    journal::Journal,
+};
+use std::path::PathBuf;
+use tokio::fs;
+
+pub(super) async fn handler(file: PathBuf, replace: bool) {
+    let content = match fs::read_to_string(&file).await {
+        Ok(content) => content,
+        Err(err) => {
+            println!("❌ Failed to read '{}': {err}", file.display());
+            return;
+        }
+    };
+    let document: StateDocument = match serde_json::from_str(&content) {
+        Ok(document) => document,
+        Err(err) => {
+            println!("❌ '{}' is not an opvm export: {err}", file.display());
+            return;
+        }
+    };
+    if document.format_version > FORMAT_VERSION {
+        println!(
+            "❌ '{}' is in format {}, this opvm supports up to {FORMAT_VERSION}",
+            file.display(),
+            document.format_version
+        );
+        return;
+    }
+    let total = document.versions.len();
+    let versions: Vec<String> = document.versions.keys().cloned().collect();
+    let db = db::open();
+    let mut journal = match Journal::begin(Action::Import) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            return;
+        }
+    };
+    if let Err(err) = journal.backup_state(&*db) {
+        println!("❌ Failed to back up the state for undo: {err}");
+        let _ = journal.remove();
+        return;
+    }
+    // kept even if the import failed halfway, so undo can clean up
+    let result = document.import(&*db, replace);
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+    match result {
+        Ok(imported) if imported == total => println!("✅ Imported {imported} versions"),
+        Ok(imported) => println!(
+            "✅ Imported {imported} versions, {} already existed",
+            total - imported
+        ),
+        Err(err) => {
+            println!("❌ Import failed: {err}");
+            return;
+        }
+    }
+    let detail = format!("from '{}'", file.display());
+    let detail = match replace {
+        true => format!("{detail}, replacing the state"),
+        false => detail,
+    };
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Import, versions).with_detail(detail),
+    );
+    db.flush().unwrap();
+}
@@ -0,0 +1,56 @@
+use super::util::{aliases_by_version, get_repo_dir, resolve_local_one};
+use crate::db::{self, Origin};
+
+pub(super) async fn handler(version: String) {
+    let db = db::open();
+    let Some(ver) = resolve_local_one(&*db, &version, "shown") else {
+        return;
+    };
+    let Some(record) = db
+        .get_version(&ver)
+        .expect("Failed to read version from db")
+    else {
+        println!("❌ No version '{ver}' in local repo");
+        return;
+    };
+    let applied = db.get_applied_ver().unwrap_or_default().as_ref() == Some(&ver);
+    let aliases = aliases_by_version(&*db).remove(&ver).unwrap_or_default();
+    let origin = match record.origin {
+        Origin::Downloaded => "downloaded",
+        Origin::Loaded => "loaded",
+        Origin::Imported => "imported",
+    };
+    println!("📦 {ver}{}", if applied { " (applied)" } else { "" });
+    let mut fields = vec![];
+    if !aliases.is_empty() {
+        fields.push(("Aliases", aliases.join(", ")));
+    }
+    if let Some(mc_ver) = record.mc_ver.as_ref() {
+        fields.push(("Minecraft", mc_ver.clone()));
+    }
+    fields.push(("Origin", origin.to_string()));
+    fields.push(("Added", record.added_at_local()));
+    if record.pinned {
+        fields.push(("Pinned", "yes".to_string()));
+    }
+    if !record.tags.is_empty() {
+        fields.push(("Tags", record.tags.join(", ")));
+    }
+    if let Some(note) = record.note.as_ref() {
+        fields.push(("Note", note.clone()));
+    }
+    if let Some(size) = record.size {
+        fields.push(("Size", format!("{size} bytes")));
+    }
+    if let Some(digest) = record.digest.as_ref() {
+        fields.push(("SHA-256", digest.clone()));
+    }
+    if let Some(source_url) = record.source_url.as_ref() {
+        fields.push(("Source", source_url.clone()));
+    }
+    let path = get_repo_dir(&*db).join(format!("{ver}.jar"));
+    fields.push(("Path", path.display().to_string()));
+    for (name, value) in fields {
+        println!("   {:<9}  {value}", name);
+    }
+}
@@ -1,7 +1,10 @@
-use super::util::sort_vers;
-use crate::db::{ivec_to_string, Database};
+use super::util::{aliases_by_version, resolve_alias, resolve_local_index, sort_vers};
+use crate::db::{self, VersionRecord};
+use crate::index::destruct_input;
 use crate::scrape::Scraper;
+use crate::version::MinecraftVersion;
 use clap::ValueEnum;
+use std::collections::BTreeMap;
 
 #[derive(ValueEnum, Clone)]
 pub enum SortBy {
@@ -16,47 +19,93 @@ pub(super) async fn handler(
     load_order: bool,
     display_time: bool,
     sort_by: Option<SortBy>,
+    group: bool,
+    tags: Vec<String>,
 ) {
-    let db = Database::new();
-    let ver_db = db.get_version_db();
-    let cache_db = db.get_cache_db();
+    let db = db::open();
     if load_order {
         let scrap = Scraper::new().await;
-        let all_opt_vers: Vec<&String> = scrap.get_all_opt_vers().iter().collect();
-        let all_opt_vers_serial = bincode::serialize(&all_opt_vers).unwrap();
-        cache_db
-            .insert("all_opt_ver", all_opt_vers_serial)
+        let all_opt_vers: Vec<String> = scrap.get_all_opt_vers().iter().cloned().collect();
+        db.set_all_opt_vers(&all_opt_vers)
             .expect("Failed to insert Optifine version into database");
     }
-    let vers_unsorted: Vec<(String, String)> = ver_db
-        .iter()
-        .filter_map(|e| match e {
-            Ok((k, v)) => Some((ivec_to_string(&k), ivec_to_string(&v))),
-            Err(_) => None,
-        })
-        .filter(|(k, _)| k.contains(pattern.as_deref().unwrap_or("")))
-        .collect();
-    let vers = sort_vers(vers_unsorted, &cache_db, sort_by.unwrap_or(SortBy::NameNew));
-    let applied_ver = cache_db
-        .get("applied_ver")
-        .expect("Failed to get applied version in db")
-        .map(|ivec| ivec_to_string(&ivec));
-    if display_time {
-        let max_key_len = vers.iter().map(|e| e.0.len()).max().unwrap_or_default();
+    // an alias lists the version it points to
+    let pattern = pattern.map(|pat| resolve_alias(&*db, &pat));
+    let mut vers_unsorted: Vec<(String, VersionRecord)> =
+        match pattern.as_deref().and_then(destruct_input) {
+            Some((mc_ver, index)) => {
+                let (resolved, invalid) = resolve_local_index(&*db, &mc_ver, &index);
+                for (raw, reason) in invalid {
+                    println!("❌ {reason} (at index '{raw}', '{mc_ver}[{raw}]')");
+                }
+                resolved
+                    .into_iter()
+                    .filter_map(|k| Some((k.clone(), db.get_version(&k).ok()??)))
+                    .collect()
+            }
+            None => db
+                .versions()
+                .expect("Failed to read versions from db")
+                .into_iter()
+                .filter(|(k, _)| k.contains(pattern.as_deref().unwrap_or("")))
+                .collect(),
+        };
+    // every tag given must be on the version
+    vers_unsorted.retain(|(_, v)| tags.iter().all(|tag| v.tags.contains(tag)));
+    let sort_by = sort_by.unwrap_or(SortBy::NameNew);
+    let oldest_first = matches!(sort_by, SortBy::NameOld | SortBy::TimeOld);
+    let vers = sort_vers(vers_unsorted, &*db, sort_by);
+    let applied_ver = db
+        .get_applied_ver()
+        .expect("Failed to get applied version in db");
+    let aliases = aliases_by_version(&*db);
+    let max_key_len = vers.iter().map(|e| e.0.len()).max().unwrap_or_default();
+    let print_vers = |vers: &[(String, VersionRecord)]| {
         for (k, v) in vers.iter() {
             let pointing = matches!(applied_ver, Some(ref v) if v == k);
-            println!(
-                "{} {} {} {}",
-                if pointing { "👉" } else { "  " },
-                k,
-                ".".repeat(max_key_len - k.len() + 3),
-                v
-            );
+            let mut line = format!("{} {}", if pointing { "👉" } else { "  " }, k);
+            if display_time {
+                line.push_str(&format!(
+                    " {} {}",
+                    ".".repeat(max_key_len - k.len() + 3),
+                    v.added_at_local()
+                ));
+            }
+            if let Some(aliases) = aliases.get(k) {
+                line.push_str(&format!(" ({})", aliases.join(", ")));
+            }
+            for tag in v.tags.iter() {
+                line.push_str(&format!(" #{tag}"));
+            }
+            if v.pinned {
+                line.push_str(" 📌");
+            }
+            println!("{line}");
         }
-    } else {
-        for (k, _) in vers.iter() {
-            let pointing = matches!(applied_ver, Some(ref v) if v == k);
-            println!("{} {}", if pointing { "👉" } else { "  " }, k);
+    };
+    if group {
+        // keep the version order inside each group, unknown versions go last
+        let mut groups: BTreeMap<Option<MinecraftVersion>, Vec<(String, VersionRecord)>> =
+            BTreeMap::new();
+        for (k, v) in vers {
+            groups
+                .entry(MinecraftVersion::of_opt_ver(&k))
+                .or_default()
+                .push((k, v));
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        if !oldest_first {
+            groups.reverse();
         }
+        groups.sort_by_key(|(mc_ver, _)| mc_ver.is_none());
+        for (mc_ver, vers) in groups {
+            match mc_ver {
+                Some(mc_ver) => println!("📦 {mc_ver}"),
+                None => println!("📦 other"),
+            }
+            print_vers(&vers);
+        }
+    } else {
+        print_vers(&vers);
     }
 }
@@ -1,24 +1,62 @@
-use super::util::get_current_time;
-use crate::db::{ivec_to_string, Database, Result, Tree};
-use std::collections::HashSet;
+use super::util::{get_repo_dir, print_plan, record_history, record_journal};
+use crate::db::{self, Action, HistoryEvent, Origin, Result, Store, VersionRecord};
+use std::{collections::HashSet, path::Path};
 use tokio::fs;
 
-pub(super) async fn handler() {
-    let db = Database::new();
-    let conf_db = db.get_config_db();
-    let repo_path = match conf_db.get("repo_dir").unwrap() {
-        Some(ivec) => ivec_to_string(&ivec),
-        None => "repo".to_string(),
-    };
+pub(super) async fn handler(dry_run: bool) {
+    let db = db::open();
+    let repo_path = get_repo_dir(&*db);
     let existed_jars = get_exised_jars(&repo_path).await;
-    let ver_db = db.get_version_db();
-    match sync_vec_to_db(ver_db, existed_jars) {
-        Ok(_) => println!("✅ Load success!"),
+    if dry_run {
+        let (to_remove, to_insert) =
+            plan_sync(&*db, existed_jars).expect("Failed to read versions from db");
+        if to_remove.is_empty() && to_insert.is_empty() {
+            println!("🔵 Nothing would change");
+        }
+        for key in to_insert {
+            let path = repo_path.join(format!("{key}.jar"));
+            print_plan(
+                &format!("{key} would be loaded"),
+                &[format!("insert '{}' into database", path.display())],
+            );
+        }
+        for key in to_remove {
+            let path = repo_path.join(format!("{key}.jar"));
+            print_plan(
+                &format!("{key} would be dropped"),
+                &[format!(
+                    "remove from database, '{}' does not exist",
+                    path.display()
+                )],
+            );
+        }
+        return;
+    }
+    match sync_vec_to_db(&*db, &repo_path, existed_jars) {
+        Ok((removed, inserted)) => {
+            println!("✅ Load success!");
+            if !removed.is_empty() || !inserted.is_empty() {
+                let detail = format!("{} loaded, {} dropped", inserted.len(), removed.len());
+                let versions = inserted
+                    .iter()
+                    .chain(removed.iter().map(|(k, _)| k))
+                    .cloned()
+                    .collect();
+                record_journal(Action::Load, |journal| {
+                    inserted.into_iter().for_each(|k| journal.loaded(k));
+                    removed.into_iter().for_each(|(k, v)| journal.removed(k, v));
+                });
+                record_history(
+                    &*db,
+                    HistoryEvent::new(Action::Load, versions).with_detail(detail),
+                );
+            }
+        }
         Err(_) => println!("❌ Load failed!"),
     }
 }
 
-async fn get_exised_jars(repo_path: &String) -> Vec<String> {
+async fn get_exised_jars(repo_path: &Path) -> Vec<String> {
     let mut readdir = fs::read_dir(repo_path)
         .await
         .expect("Failed to read repo dir");
@@ -39,23 +77,41 @@ async fn get_exised_jars(repo_path: &String) -> Vec<String> {
     existed_jars
 }
 
-fn sync_vec_to_db(db: Tree, vec: Vec<String>) -> Result<()> {
-    let mut keys_in_db = HashSet::new();
-    for entry in db.iter() {
-        let (k, _) = entry?;
-        keys_in_db.insert(ivec_to_string(&k));
-    }
+/// The entries removed and the versions inserted by a sync
+type Synced = (Vec<(String, VersionRecord)>, Vec<String>);
+
+/// Entries without jar and jars without entry, as (to remove, to insert)
+fn plan_sync(db: &dyn Store, vec: Vec<String>) -> Result<(Vec<String>, Vec<String>)> {
+    let keys_in_db: HashSet<String> = db.versions()?.into_iter().map(|(k, _)| k).collect();
     let vec_set: HashSet<String> = vec.into_iter().collect();
 
-    let to_remove: Vec<&String> = keys_in_db.difference(&vec_set).collect();
-    let to_insert: Vec<&String> = vec_set.difference(&keys_in_db).collect();
+    let mut to_remove: Vec<String> = keys_in_db.difference(&vec_set).cloned().collect();
+    let mut to_insert: Vec<String> = vec_set.difference(&keys_in_db).cloned().collect();
+    to_remove.sort();
+    to_insert.sort();
+    Ok((to_remove, to_insert))
+}
+
+/// Make the database match the jars
+fn sync_vec_to_db(db: &dyn Store, repo_path: &Path, vec: Vec<String>) -> Result<Synced> {
+    let (to_remove, to_insert) = plan_sync(db, vec)?;
 
+    let mut removed = vec![];
     for key in to_remove {
-        db.remove(key)?;
+        if let Some(record) = db.remove_version(&key)? {
+            removed.push((key, record));
+        }
     }
+    let mut inserted = vec![];
     for key in to_insert {
-        let time = get_current_time();
-        db.insert(key, &time[..])?;
+        let path = repo_path.join(format!("{key}.jar"));
+        match VersionRecord::from_file(&key, &path, Origin::Loaded) {
+            Ok(record) => {
+                db.insert_version(&key, &record)?;
+                inserted.push(key);
+            }
+            Err(_) => println!("❌ Failed to read '{}'", path.display()),
+        }
     }
-    Ok(())
+    Ok((removed, inserted))
 }
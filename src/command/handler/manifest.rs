@@ -0,0 +1,33 @@
+use super::{util::get_repo_dir, util::sort_vers, ListSortBy};
+use crate::{
+    db::{self, file_info},
+    manifest::{Manifest, ManifestEntry},
+};
+use std::path::PathBuf;
+
+/// Write all local versions with their digests into the manifest
+pub(super) async fn freeze(file: PathBuf) {
+    let db = db::open();
+    let vers = sort_vers(
+        db.versions().expect("Failed to read versions from db"),
+        &*db,
+        ListSortBy::NameNew,
+    );
+    let repo_dir = get_repo_dir(&*db);
+    let mut versions = vec![];
+    for (ver, _) in vers {
+        // digest of the jar as it is now, the record may be older than that
+        match file_info(&repo_dir.join(format!("{ver}.jar"))) {
+            Ok((_, digest)) => versions.push(ManifestEntry {
+                spec: ver,
+                sha256: Some(digest),
+            }),
+            Err(_) => println!("❌ {ver} cannot be read, it is left out"),
+        }
+    }
+    let count = versions.len();
+    match (Manifest { versions }).write(&file) {
+        Ok(_) => println!("✅ Wrote {count} versions into '{}'", file.display()),
+        Err(reason) => println!("❌ {reason}"),
+    }
+}
@@ -1,35 +1,217 @@
-use crate::command::{Cli, Commands};
+use crate::{
+    command::{AliasCommand, Cli, Commands, ManifestCommand, TrashCommand},
+    db, home,
+    lock::{Lock, LockMode},
+};
 
 mod add;
+mod alias;
 mod apply;
 mod config;
+mod doctor;
+mod export;
+mod gc;
+mod history;
+mod import;
+mod info;
 mod list;
 mod load;
+mod manifest;
+mod outdated;
+mod pin;
+mod prompt;
 mod remove;
 mod search;
+mod sync;
+mod tag;
+mod trash;
+mod undo;
+mod update;
 mod util;
 
+pub(super) use gc::{parse_keep, parse_size};
 pub(super) use list::SortBy as ListSortBy;
 
 pub async fn command_handler(cli: Cli) {
+    run(cli).await;
+    // the lock is released by now, so no stale `lock.pid` is left behind
+    if prompt::aborted() {
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) {
+    home::init(cli.home);
+    prompt::init(cli.yes, cli.no_input);
     let cmd = cli.command;
+    let mode = lock_mode(&cmd);
+    // moving an old installation writes, even for a command that only reads
+    let lock_mode = if home::migration_pending() {
+        LockMode::Exclusive
+    } else {
+        mode
+    };
+    let _lock = match Lock::acquire(lock_mode, cli.wait) {
+        Ok(lock) => lock,
+        Err(reason) => {
+            println!("{reason}");
+            std::process::exit(1);
+        }
+    };
+    if home::migration_pending() {
+        let question = format!(
+            "🔰 Found an old opvm database '.db' and 'repo/' in current directory, move them into '{}'?",
+            home::get().display()
+        );
+        if prompt::confirm(&question) {
+            home::migrate();
+        } else if prompt::aborted() {
+            return;
+        }
+    }
+    db::init(cli.store);
     match cmd {
-        Commands::Add { versions } => add::handler(versions).await,
-        Commands::Remove { patterns } => remove::handler(patterns).await,
-        Commands::Apply { version } => apply::handler(version).await,
+        Commands::Add { versions, dry_run } => add::handler(versions, dry_run).await,
+        Commands::Remove {
+            patterns,
+            exact,
+            glob,
+            regex,
+            all,
+            force,
+            dry_run,
+        } => {
+            let mode = match (exact, glob, regex) {
+                (true, _, _) => remove::MatchMode::Exact,
+                (_, true, _) => remove::MatchMode::Glob,
+                (_, _, true) => remove::MatchMode::Regex,
+                _ => remove::MatchMode::Substring,
+            };
+            remove::handler(patterns, mode, all, force, dry_run).await
+        }
+        Commands::Apply { version, dry_run } => apply::handler(version, dry_run).await,
         Commands::Config {
             minecraft_dir,
             java_path,
             repo_dir,
+            trash_days,
             test,
-        } => config::handler(minecraft_dir, java_path, repo_dir, test).await,
+        } => config::handler(minecraft_dir, java_path, repo_dir, trash_days, test).await,
         Commands::List {
             pattern,
             load_order,
             time,
             by,
-        } => list::handler(pattern, load_order, time, by).await,
-        Commands::Load => load::handler().await,
+            group,
+            tags,
+        } => list::handler(pattern, load_order, time, by, group, tags).await,
+        Commands::Load { dry_run } => load::handler(dry_run).await,
+        Commands::Info { version } => info::handler(version).await,
+        Commands::Tag {
+            version,
+            tags,
+            remove,
+        } => tag::tag(version, tags, remove).await,
+        Commands::Note {
+            version,
+            text,
+            clear,
+        } => tag::note(version, text, clear).await,
+        Commands::Pin { versions } => pin::handler(versions, true).await,
+        Commands::Unpin { versions } => pin::handler(versions, false).await,
+        Commands::Alias { command } => match command {
+            AliasCommand::Set { alias, version } => alias::set(alias, version).await,
+            AliasCommand::Remove { alias } => alias::remove(alias).await,
+            AliasCommand::List => alias::list().await,
+        },
         Commands::Search { version } => search::handler(version).await,
+        Commands::Doctor { fix } => doctor::handler(fix).await,
+        Commands::Export { file } => export::handler(file).await,
+        Commands::Import { file, replace } => import::handler(file, replace).await,
+        Commands::Update {
+            versions,
+            stable,
+            prune,
+            reapply,
+            force,
+        } => update::handler(versions, stable, prune, reapply, force).await,
+        Commands::Outdated { versions, stable } => outdated::handler(versions, stable).await,
+        Commands::Gc {
+            keep,
+            drop_previews,
+            max_age,
+            max_size,
+            force,
+            dry_run,
+        } => {
+            let policy = gc::Policy {
+                keep,
+                drop_previews,
+                max_age,
+                max_size,
+            };
+            gc::handler(policy, force, dry_run).await
+        }
+        Commands::Trash { command } => match command {
+            TrashCommand::List => trash::list().await,
+            TrashCommand::Restore { versions } => trash::restore(versions).await,
+            TrashCommand::Empty => trash::empty().await,
+        },
+        Commands::Sync { file, prune } => sync::handler(file, prune).await,
+        Commands::Manifest {
+            command: ManifestCommand::Freeze { file },
+        } => manifest::freeze(file).await,
+        Commands::Undo => undo::handler().await,
+        Commands::History {
+            command,
+            version,
+            since,
+            until,
+            limit,
+        } => history::handler(command, version, since, until, limit).await,
+    }
+    if mode == LockMode::Exclusive {
+        trash::expire();
+    }
+}
+
+/// Commands that only read may run along with each other
+fn lock_mode(cmd: &Commands) -> LockMode {
+    match cmd {
+        Commands::List {
+            load_order: false, ..
+        } => LockMode::Shared,
+        Commands::Search { .. }
+        | Commands::Info { .. }
+        | Commands::Trash {
+            command: TrashCommand::List,
+        }
+        | Commands::Alias {
+            command: AliasCommand::List,
+        }
+        | Commands::Export { .. }
+        | Commands::History { .. }
+        | Commands::Outdated { .. }
+        | Commands::Manifest { .. } => LockMode::Shared,
+        Commands::Doctor { fix: false } => LockMode::Shared,
+        Commands::Tag { tags, .. } if tags.is_empty() => LockMode::Shared,
+        Commands::Note {
+            text: None,
+            clear: false,
+            ..
+        } => LockMode::Shared,
+        Commands::Add { dry_run: true, .. }
+        | Commands::Remove { dry_run: true, .. }
+        | Commands::Apply { dry_run: true, .. }
+        | Commands::Load { dry_run: true }
+        | Commands::Gc { dry_run: true, .. } => LockMode::Shared,
+        Commands::Config {
+            minecraft_dir: None,
+            java_path: None,
+            repo_dir: None,
+            trash_days: None,
+            test: true,
+        } => LockMode::Shared,
+        _ => LockMode::Exclusive,
     }
 }
@@ -0,0 +1,105 @@
+use super::util::{has_up_to_date, local_lines, newest_of};
+use crate::{
+    db,
+    scrape::Scraper,
+    version::{MinecraftVersion, OptifineVersion},
+};
+
+/// One row of the report
+struct Row {
+    mc_ver: MinecraftVersion,
+    installed: String,
+    stable: Option<String>,
+    preview: Option<String>,
+    outdated: bool,
+}
+
+pub(super) async fn handler(mc_vers: Vec<String>, stable_only: bool) {
+    let db = db::open();
+    let mut lines = local_lines(&*db);
+    // nothing is written, the store can be released for others right away
+    drop(db);
+    if !mc_vers.is_empty() {
+        let wanted: Vec<MinecraftVersion> = mc_vers
+            .iter()
+            .filter_map(|ver| match ver.parse() {
+                Ok(mc_ver) => Some(mc_ver),
+                Err(_) => {
+                    println!("❌ No such Minecraft version '{ver}'");
+                    None
+                }
+            })
+            .collect();
+        lines.retain(|mc_ver, _| wanted.contains(mc_ver));
+    }
+    if lines.is_empty() {
+        println!("🔵 No local version to check");
+        return;
+    }
+    let scrap = Scraper::new().await;
+
+    let mut rows = vec![];
+    for (mc_ver, local) in lines.into_iter().rev() {
+        let remote = scrap.get_opt_vers(&mc_ver.to_string());
+        let stable = remote.iter().find(|ver| !Scraper::is_preview(ver)).cloned();
+        // a preview only matters before its release
+        let preview = remote
+            .iter()
+            .find(|ver| Scraper::is_preview(ver))
+            .filter(|preview| {
+                let stable = stable.as_ref().and_then(|ver| ver.parse().ok());
+                match (preview.parse::<OptifineVersion>(), stable) {
+                    (Ok(preview), Some(stable)) => preview > stable,
+                    _ => true,
+                }
+            })
+            .cloned();
+        let outdated = stable
+            .iter()
+            .chain(preview.iter().filter(|_| !stable_only))
+            .any(|ver| !has_up_to_date(&local, ver));
+        rows.push(Row {
+            installed: newest_of(&local).cloned().unwrap_or_default(),
+            mc_ver,
+            stable,
+            preview,
+            outdated,
+        });
+    }
+
+    let width = |f: fn(&Row) -> usize, title: &str| {
+        rows.iter()
+            .map(f)
+            .max()
+            .unwrap_or_default()
+            .max(title.len())
+    };
+    let mc_width = width(|row| row.mc_ver.to_string().len(), "Minecraft");
+    let installed_width = width(|row| row.installed.len(), "Installed");
+    let stable_width = width(
+        |row| row.stable.as_ref().map_or(1, |ver| ver.len()),
+        "Stable",
+    );
+    println!(
+        "   {:<mc_width$}  {:<installed_width$}  {:<stable_width$}  Preview",
+        "Minecraft", "Installed", "Stable"
+    );
+    for row in rows.iter() {
+        println!(
+            "{} {:<mc_width$}  {:<installed_width$}  {:<stable_width$}  {}",
+            if row.outdated { "📦" } else { "✅" },
+            row.mc_ver.to_string(),
+            row.installed,
+            row.stable.as_deref().unwrap_or("-"),
+            row.preview.as_deref().unwrap_or("-"),
+        );
+    }
+
+    let outdated = rows.iter().filter(|row| row.outdated).count();
+    if outdated > 0 {
+        println!("👉 {outdated} Minecraft versions can be updated, run `opvm update`");
+        // for scripts and cron jobs
+        std::process::exit(1);
+    }
+    println!("👉 All up to date");
+}
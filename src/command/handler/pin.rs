@@ -0,0 +1,52 @@
+use super::util::{record_history, record_journal, resolve_local_one};
+use crate::db::{self, Action, HistoryEvent};
+
+/// Pin or unpin some local versions
+pub(super) async fn handler(versions: Vec<String>, pinned: bool) {
+    let db = db::open();
+    let verb = if pinned { "pinned" } else { "unpinned" };
+    let mut changed = vec![];
+    let mut failed = 0;
+    for version in versions {
+        let Some(ver) = resolve_local_one(&*db, &version, verb) else {
+            failed += 1;
+            continue;
+        };
+        let Some(record) = db
+            .get_version(&ver)
+            .expect("Failed to read version from db")
+        else {
+            println!("❌ No version '{ver}' in local repo");
+            failed += 1;
+            continue;
+        };
+        if record.pinned == pinned {
+            println!("🔵 {ver} is {verb} already");
+            continue;
+        }
+        db.insert_version(
+            &ver,
+            &db::VersionRecord {
+                pinned,
+                ..record.clone()
+            },
+        )
+        .expect("Failed to write version into db");
+        println!("✅ {ver} {verb}");
+        changed.push((ver, record));
+    }
+    if !changed.is_empty() {
+        let action = if pinned { Action::Pin } else { Action::Unpin };
+        let versions = changed.iter().map(|(ver, _)| ver.clone()).collect();
+        record_journal(action, |journal| {
+            for (ver, record) in changed {
+                journal.changed(ver, record);
+            }
+        });
+        record_history(&*db, HistoryEvent::new(action, versions));
+    }
+    db.flush().unwrap();
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
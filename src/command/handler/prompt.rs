@@ -0,0 +1,141 @@
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+/// How to deal with prompts, from `--yes` and `--no-input`
+struct Settings {
+    yes: bool,
+    no_input: bool,
+}
+
+/// Why a question got no answer
+#[derive(Debug, PartialEq, Eq)]
+enum Unanswered {
+    /// `--no-input`, or stdin is no terminal
+    Nobody,
+    /// stdin is closed or cannot be read
+    Eof,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static ABORTED: AtomicBool = AtomicBool::new(false);
+
+pub(super) fn init(yes: bool, no_input: bool) {
+    SETTINGS.get_or_init(|| Settings { yes, no_input });
+}
+
+/// Ask a yes/no question, no by default.
+///
+/// `--yes` answers yes without asking. If nobody can answer,
+/// namely `--no-input` or stdin is no terminal, the answer is no and the command is aborted
+/// rather than block, see `aborted`.
+pub(super) fn confirm(question: &str) -> bool {
+    if settings().yes {
+        println!("{question} [y/N] y (--yes)");
+        return true;
+    }
+    match read_answer(&format!("{question} [y/N] ")) {
+        Ok(answer) => answer == "Y" || answer == "y",
+        Err(unanswered) => abort(unanswered, "a confirmation", "pass --yes to go on"),
+    }
+}
+
+/// Ask for a line of text, e.g. a path, `None` if the command is aborted
+pub(super) fn input(question: &str) -> Option<String> {
+    match read_answer(question) {
+        Ok(answer) => Some(answer),
+        Err(unanswered) => abort(unanswered, "input", "give it by options instead"),
+    }
+}
+
+/// Whether a prompt got no answer, opvm then exits with 1 once the command returns
+pub(super) fn aborted() -> bool {
+    ABORTED.load(Ordering::Relaxed)
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings {
+        yes: false,
+        no_input: false,
+    })
+}
+
+/// The trimmed answer, or why there is none
+fn read_answer(question: &str) -> Result<String, Unanswered> {
+    let stdin = io::stdin();
+    let interactive = !settings().no_input && stdin.is_terminal();
+    read_answer_from(question, interactive, &mut stdin.lock())
+}
+
+/// `read_answer` from any input, `interactive` tells whether somebody is there to answer
+fn read_answer_from(
+    question: &str,
+    interactive: bool,
+    input: &mut impl BufRead,
+) -> Result<String, Unanswered> {
+    if !interactive {
+        return Err(Unanswered::Nobody);
+    }
+    print!("{question}");
+    io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    // nothing to read means stdin is closed
+    match input.read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            println!();
+            Err(Unanswered::Eof)
+        }
+        Ok(_) => Ok(answer.trim().to_string()),
+    }
+}
+
+/// Tell why the command cannot go on, the caller returns so the lock is released before exit
+fn abort<T: Default>(unanswered: Unanswered, what: &str, hint: &str) -> T {
+    let why = match unanswered {
+        Unanswered::Eof => "stdin gave no input (EOF)",
+        Unanswered::Nobody if settings().no_input => "--no-input is given",
+        Unanswered::Nobody => "stdin is not a terminal",
+    };
+    println!("🛑 opvm needs {what}, but {why} ({hint})");
+    ABORTED.store(true, Ordering::Relaxed);
+    T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn nobody_answers_without_terminal() {
+        let mut input = Cursor::new("y\n");
+        assert_eq!(
+            read_answer_from("keep going?", false, &mut input),
+            Err(Unanswered::Nobody)
+        );
+        // nothing is taken from the input
+        assert_eq!(input.position(), 0);
+    }
+
+    #[test]
+    fn answers_are_trimmed() {
+        let mut input = Cursor::new("  y \n/opt/java\n");
+        assert_eq!(
+            read_answer_from("keep going?", true, &mut input).as_deref(),
+            Ok("y")
+        );
+        assert_eq!(
+            read_answer_from("java path?", true, &mut input).as_deref(),
+            Ok("/opt/java")
+        );
+        // stdin is closed
+        assert_eq!(
+            read_answer_from("again?", true, &mut input),
+            Err(Unanswered::Eof)
+        );
+    }
+}
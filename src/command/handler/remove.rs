@@ -1,54 +1,134 @@
-use crate::db::{ivec_to_string, Database, Tree};
-use std::{collections::HashSet, io::Write, path::Path, sync::Arc};
-use tokio::fs;
+use super::{
+    prompt,
+    util::{
+        get_repo_dir, is_pinned, print_plan, record_history, resolve_alias, resolve_local_index,
+        warn_dangling_aliases,
+    },
+};
+use crate::{
+    db::{self, Action, Database, HistoryEvent, Store, VersionRecord},
+    index::destruct_input,
+    journal::Journal,
+    trash,
+};
+use regex::Regex;
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
-pub(super) async fn handler(patterns: Vec<String>) {
-    if patterns.is_empty() {
+/// How a pattern of `remove` matches version names
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum MatchMode {
+    /// Names containing the pattern, the default
+    Substring,
+    /// The name itself
+    Exact,
+    /// Whole names by `*` (any characters), `?` (one character)
+    /// and `[...]` (one character in the class, `[!...]` for one not in it)
+    Glob,
+    /// Names a regular expression matches somewhere in
+    Regex,
+}
+
+pub(super) async fn handler(
+    patterns: Vec<String>,
+    mode: MatchMode,
+    all: bool,
+    force: bool,
+    dry_run: bool,
+) {
+    // a shell variable expanding to nothing must not wipe the repo
+    if patterns.iter().any(|pat| pat.is_empty()) {
+        println!("❌ Empty pattern is not allowed, use --all to remove every version");
+        std::process::exit(1);
+    }
+    if patterns.is_empty() && !all {
         return;
     }
-    let db = Database::new();
-    let ver_db = db.get_version_db();
-    let conf_db = db.get_config_db();
-    let current_files: Vec<String> = ver_db
-        .iter()
-        .map(|e| e.expect("Failed to get version from db"))
-        .map(|(k, _)| ivec_to_string(&k))
+    let db = db::open();
+    let current_files: Vec<String> = db
+        .versions()
+        .expect("Failed to get version from db")
+        .into_iter()
+        .map(|(k, _)| k)
         .collect();
-    let to_delete = gather_2delete_files(current_files, &patterns);
+    let (indexed, plain_patterns) = split_patterns(&*db, &current_files, patterns, mode);
+    let mut to_delete = match all {
+        true => current_files.into_iter().collect(),
+        false => match gather_2delete_files(current_files, &plain_patterns, mode) {
+            Ok(to_delete) => to_delete,
+            Err(reason) => {
+                println!("❌ {reason}");
+                std::process::exit(1);
+            }
+        },
+    };
+    to_delete.extend(indexed);
+    if !force {
+        let mut pinned: Vec<String> = to_delete
+            .iter()
+            .filter(|name| is_pinned(&*db, name))
+            .cloned()
+            .collect();
+        pinned.sort();
+        for name in pinned {
+            println!("📌 {name} is pinned, keep it (use --force to remove it)");
+            to_delete.remove(&name);
+        }
+    }
     if to_delete.is_empty() {
         return;
+    } else if dry_run {
+        let repo_dir = get_repo_dir(&*db);
+        let applied_ver = db.get_applied_ver().unwrap_or_default();
+        let mut to_delete: Vec<String> = to_delete.into_iter().collect();
+        to_delete.sort();
+        for name in to_delete {
+            let mut steps = vec![
+                format!(
+                    "move '{}' into trash",
+                    repo_dir.join(format!("{name}.jar")).display()
+                ),
+                "remove from database".to_string(),
+            ];
+            if applied_ver.as_ref() == Some(&name) {
+                steps.push("it is the applied version, which stays in launcher".to_string());
+            }
+            print_plan(&format!("{name} would be removed"), &steps);
+        }
+        return;
     } else if to_delete.len() > 1 {
-        print!(
-            "🔰 This operation will delete {} files, keep going? [y/N] ",
+        let question = format!(
+            "🔰 This operation will delete {} files, keep going?",
             to_delete.len()
         );
-        std::io::stdout().flush().unwrap();
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-        if input != "Y" && input != "y" {
+        if !prompt::confirm(&question) {
             return;
         }
     }
-    let max_display_len = to_delete.iter().map(|s| s.len()).max().unwrap();
-    let base_path = match conf_db.get("repo_dir").unwrap() {
-        Some(ivec) => ivec_to_string(&ivec),
-        None => "repo".to_string(),
+    // jars are moved into trash, so `opvm undo` and `opvm trash restore` can bring them back
+    let mut journal = match Journal::begin(Action::Remove) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            return;
+        }
     };
+    let max_display_len = to_delete.iter().map(|s| s.len()).max().unwrap();
+    let base_path = get_repo_dir(&*db);
     let base_path = Arc::new(base_path);
     let futures = to_delete
         .into_iter()
-        .map(|name| (ver_db.clone(), base_path.clone(), Arc::new(name)))
-        .map(|(ver_db, base_path, name)| {
+        .map(|name| (db.clone(), base_path.clone(), Arc::new(name)))
+        .map(|(db, base_path, name)| {
             tokio::spawn(async move {
-                handle_one_file(ver_db, base_path, name.clone())
+                handle_one_file(db, base_path, name.clone())
                     .await
-                    .map(|_| {
+                    .map(|trashed| {
                         println!(
                             "✅ {} {} success!",
                             name,
                             ".".repeat(max_display_len - name.len() + 3)
-                        )
+                        );
+                        (name.to_string(), trashed)
                     })
                     .map_err(|_| {
                         println!(
@@ -69,30 +149,243 @@ pub(super) async fn handler(patterns: Vec<String>) {
     if recap.0 > 1 || recap.1 > 0 {
         println!("👉 {} success / {} failed", recap.0, recap.1);
     }
+    let mut removed: Vec<(String, (VersionRecord, String))> =
+        results.into_iter().filter_map(|r| r.ok()?.ok()).collect();
+    removed.sort_by(|a, b| a.0.cmp(&b.0));
+    let names: Vec<String> = removed.iter().map(|(name, _)| name.clone()).collect();
+    for (name, (record, id)) in removed {
+        journal.removed(name.clone(), record);
+        journal.trashed(name, id);
+    }
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+    warn_dangling_aliases(&*db, &names);
+    if !names.is_empty() {
+        record_history(&*db, HistoryEvent::new(Action::Remove, names));
+    }
 }
 
-fn gather_2delete_files(current_files: Vec<String>, patterns: &Vec<String>) -> HashSet<String> {
-    let mut to_delete = HashSet::new();
-    'outer: for filename in current_files {
-        for pat in patterns {
-            if filename.contains(pat) {
-                to_delete.insert(filename);
-                continue 'outer;
+/// Index specs and aliases resolved into exact versions, and the rest left as patterns
+fn split_patterns(
+    db: &dyn Store,
+    current_files: &[String],
+    patterns: Vec<String>,
+    mode: MatchMode,
+) -> (HashSet<String>, Vec<String>) {
+    let mut indexed = HashSet::new();
+    let mut plain_patterns = vec![];
+    for pat in patterns {
+        let is_name = matches!(mode, MatchMode::Substring | MatchMode::Exact);
+        match destruct_input(&pat) {
+            // other modes take `[` as they do, e.g. a class of a glob
+            Some((mc_ver, index)) if mode == MatchMode::Substring => {
+                let (resolved, invalid) = resolve_local_index(db, &mc_ver, &index);
+                for (raw, reason) in invalid {
+                    println!("❌ {reason} (at index '{raw}', '{mc_ver}[{raw}]')");
+                }
+                indexed.extend(resolved);
             }
+            // an alias means exactly its version, not a pattern
+            _ if is_name && db.get_alias(&pat).unwrap().is_some() => {
+                let ver = resolve_alias(db, &pat);
+                if current_files.contains(&ver) {
+                    indexed.insert(ver);
+                }
+            }
+            _ => plain_patterns.push(pat),
         }
     }
-    to_delete
+    (indexed, plain_patterns)
 }
 
-async fn handle_one_file(db: Tree, base: Arc<String>, name: Arc<String>) -> Result<(), String> {
-    let filename = format!("{}.jar", &*name);
-    let file = Path::new(base.as_ref()).join(&filename);
-    fs::remove_file(file)
-        .await
-        .map_err(|_| "Failed to remove file".to_string())
-        .and_then(|_| {
-            db.remove(&*name)
-                .map_err(|_| "Failed to remove entry in db".to_string())
-        })?;
-    Ok(())
+fn gather_2delete_files(
+    current_files: Vec<String>,
+    patterns: &[String],
+    mode: MatchMode,
+) -> Result<HashSet<String>, String> {
+    // every mode comes down to a regex
+    let mut matchers = vec![];
+    for pat in patterns {
+        let re = match mode {
+            MatchMode::Substring => regex::escape(pat),
+            MatchMode::Exact => format!("^{}$", regex::escape(pat)),
+            MatchMode::Glob => glob_to_regex(pat),
+            MatchMode::Regex => pat.clone(),
+        };
+        let matcher = Regex::new(&re).map_err(|err| format!("Invalid pattern '{pat}': {err}"))?;
+        matchers.push(matcher);
+    }
+    for (pat, re) in patterns.iter().zip(matchers.iter()) {
+        if !current_files.iter().any(|filename| re.is_match(filename)) {
+            println!("🔵 '{pat}' matches no local version");
+        }
+    }
+    let to_delete = current_files
+        .into_iter()
+        .filter(|filename| matchers.iter().any(|re| re.is_match(filename)))
+        .collect();
+    Ok(to_delete)
+}
+
+/// A regex matching the whole name as the glob does, a `[` without its `]` is taken as is
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut re = String::from("^");
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '[' => match glob_class(&chars[i + 1..]) {
+                Some((class, len)) => {
+                    re.push_str(&class);
+                    i += len;
+                }
+                None => re.push_str(r"\["),
+            },
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    re.push('$');
+    re
+}
+
+/// The regex class of a glob class after its `[`, and the chars taken up to its `]`
+fn glob_class(chars: &[char]) -> Option<(String, usize)> {
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    let start = usize::from(negated);
+    // a `]` right after the `[` is a member, not the end
+    let end = start + 1 + chars.get(start + 1..)?.iter().position(|&c| c == ']')?;
+    let mut class = String::from(if negated { "[^" } else { "[" });
+    for &c in chars[start..end].iter() {
+        // characters with a meaning inside a regex class, `-` keeps its meaning of a range
+        if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~') {
+            class.push('\\');
+        }
+        class.push(c);
+    }
+    class.push(']');
+    Some((class, end + 1))
+}
+
+/// Move the jar into trash and remove its entry, returns the record removed and the trash item
+pub(super) async fn handle_one_file(
+    db: Database,
+    base: Arc<PathBuf>,
+    name: Arc<String>,
+) -> Result<(VersionRecord, String), String> {
+    let record = db
+        .get_version(&name)
+        .map_err(|_| "Failed to read entry in db".to_string())?
+        .ok_or_else(|| "No entry in db".to_string())?;
+    let file = base.join(format!("{}.jar", &*name));
+    let id = trash::put(&name, &file, &record).map_err(|_| "Failed to remove file".to_string())?;
+    db.remove_version(&name)
+        .map_err(|_| "Failed to remove entry in db".to_string())?;
+    Ok((record, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    const LOCAL: [&str; 5] = [
+        "1.16.5_HD_U_G8",
+        "1.16.5_HD_U_G7",
+        "1.16.5_HD_U_H1_pre2",
+        "1.20.4_HD_U_I7",
+        "custom",
+    ];
+
+    /// Versions removed by `patterns` in `mode`, sorted, or why the patterns are invalid
+    fn removed(patterns: &[&str], mode: MatchMode) -> Result<Vec<String>, String> {
+        let db = db::memory();
+        for ver in LOCAL {
+            db.insert_version(ver, &VersionRecord::at(Utc::now()))
+                .unwrap();
+        }
+        db.set_alias("night", Some("1.20.4_HD_U_I7")).unwrap();
+        let current_files: Vec<String> = LOCAL.iter().map(|ver| ver.to_string()).collect();
+        let patterns = patterns.iter().map(|pat| pat.to_string()).collect();
+        let (indexed, plain) = split_patterns(&*db, &current_files, patterns, mode);
+        let mut removed: Vec<String> = gather_2delete_files(current_files, &plain, mode)?
+            .into_iter()
+            .chain(indexed)
+            .collect();
+        removed.sort();
+        Ok(removed)
+    }
+
+    #[test]
+    fn substring_mode() {
+        assert_eq!(
+            removed(&["G", "night"], MatchMode::Substring).unwrap(),
+            ["1.16.5_HD_U_G7", "1.16.5_HD_U_G8", "1.20.4_HD_U_I7"]
+        );
+        // the newest is the preview of H1
+        assert_eq!(
+            removed(&["1.16.5[2~]"], MatchMode::Substring).unwrap(),
+            ["1.16.5_HD_U_G7", "1.16.5_HD_U_G8"]
+        );
+    }
+
+    #[test]
+    fn exact_mode() {
+        assert_eq!(
+            removed(&["1.16.5_HD_U_G8", "1.16.5", "night"], MatchMode::Exact).unwrap(),
+            ["1.16.5_HD_U_G8", "1.20.4_HD_U_I7"]
+        );
+        // an index spec is a name like any other
+        assert!(removed(&["1.16.5[1]"], MatchMode::Exact)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn glob_mode() {
+        assert_eq!(
+            removed(&["1.16.5_*"], MatchMode::Glob).unwrap(),
+            ["1.16.5_HD_U_G7", "1.16.5_HD_U_G8", "1.16.5_HD_U_H1_pre2"]
+        );
+        assert_eq!(
+            removed(&["1.16.5_HD_U_G?", "custo"], MatchMode::Glob).unwrap(),
+            ["1.16.5_HD_U_G7", "1.16.5_HD_U_G8"]
+        );
+        assert_eq!(
+            removed(&["*_G[1-7]", "*_U_[!GH]*"], MatchMode::Glob).unwrap(),
+            ["1.16.5_HD_U_G7", "1.20.4_HD_U_I7"]
+        );
+        // an index spec is a glob class, and an alias no name
+        assert!(removed(&["1.16.5[1]", "night"], MatchMode::Glob)
+            .unwrap()
+            .is_empty());
+        // a `[` without `]` is taken as is
+        assert!(removed(&["1.16.5[*"], MatchMode::Glob).unwrap().is_empty());
+    }
+
+    #[test]
+    fn regex_mode() {
+        assert_eq!(
+            removed(&[r"_G\d$", "^cus"], MatchMode::Regex).unwrap(),
+            ["1.16.5_HD_U_G7", "1.16.5_HD_U_G8", "custom"]
+        );
+        // an index spec is a regex class
+        assert_eq!(
+            removed(&["1.20.4[_]"], MatchMode::Regex).unwrap(),
+            ["1.20.4_HD_U_I7"]
+        );
+        assert!(removed(&["("], MatchMode::Regex).is_err());
+    }
+
+    #[test]
+    fn globs_into_regexes() {
+        assert_eq!(glob_to_regex("1.16.5_*"), r"^1\.16\.5_.*$");
+        assert_eq!(glob_to_regex("G?"), "^G.$");
+        assert_eq!(glob_to_regex("[!a-c]"), "^[^a-c]$");
+        assert_eq!(glob_to_regex("[]^]"), r"^[\]\^]$");
+        assert_eq!(glob_to_regex("[]"), r"^\[\]$");
+    }
 }
@@ -1,13 +1,14 @@
-use crate::{db::Database, scrape::Scraper};
+use crate::{db, scrape::Scraper, version::MinecraftVersion};
+use std::collections::BTreeMap;
 
 pub(super) async fn handler(version: Option<String>) {
     let scrape = Scraper::new().await;
     if let Some(version) = version {
         println!("📦 Avaliable Optifine version for {version}");
         let vers = scrape.get_opt_vers(&version[..]);
-        let db = Database::new().get_version_db();
+        let db = db::open();
         for ver in vers {
-            if db.get(ver).unwrap().is_some() {
+            if db.contains_version(ver).unwrap() {
                 println!("🟢 {ver}");
             } else {
                 println!("🔘 {ver}");
@@ -15,36 +16,30 @@ pub(super) async fn handler(version: Option<String>) {
         }
     } else {
         println!("📦 Avaliable Minecraft version");
-        const COL_COUNT: usize = 6;
-        let vers: Vec<&String> = scrape.get_mc_vers().iter().collect();
-        // Use `vers.len() / COL_COUNT` will represent like this:
-        // if vers.len() % COL_COUNT is 0
-        //   -> 6 full columns
-        // else
-        //   -> 6 full columns + 1 remain column
-        // But for smaller element, this method will cause some chaos
-        // For example, when #element is 10, it will become 10 columns
-        // By the fact that existed mc ver is large enough, choose this method for simplicity
-        let vers_chucks: Vec<Vec<&String>> = vers
-            .chunks(vers.len() / COL_COUNT)
-            .map(|e| e.to_vec())
-            .collect();
-        let vers_repr = transpose(&vers_chucks);
-        for row in vers_repr {
-            for ver in row {
-                print!("   {:<6}", ver);
+        // group by version line, newest line first, newest version first
+        let mut groups: BTreeMap<(u32, u32), Vec<MinecraftVersion>> = BTreeMap::new();
+        let mut others = vec![];
+        for ver in scrape.get_mc_vers() {
+            match ver.parse::<MinecraftVersion>() {
+                Ok(mc_ver) => groups.entry(mc_ver.group()).or_default().push(mc_ver),
+                Err(_) => others.push(ver),
+            }
+        }
+        for vers in groups.values_mut().rev() {
+            vers.sort_by(|a, b| b.cmp(a));
+            let group_name = vers[0].group_name();
+            print!("   {group_name:<6} |");
+            for ver in vers {
+                print!(" {:<7}", ver.to_string());
             }
             println!();
         }
-    }
-}
-
-fn transpose<T: Clone>(matrix: &Vec<Vec<T>>) -> Vec<Vec<T>> {
-    let mut transposed = vec![Vec::with_capacity(matrix.len()); matrix[0].len()];
-    for row in matrix {
-        for (j, val) in row.iter().enumerate() {
-            transposed[j].push(val.clone());
+        if !others.is_empty() {
+            print!("   {:<6} |", "other");
+            for ver in others {
+                print!(" {ver:<7}");
+            }
+            println!();
         }
     }
-    transposed
 }
@@ -0,0 +1,184 @@
+use super::{
+    add::{download, resolve_remote},
+    remove::handle_one_file,
+    util::{get_repo_dir, is_pinned, record_history, warn_dangling_aliases},
+};
+use crate::{
+    db::{self, file_info, Action, HistoryEvent},
+    journal::Journal,
+    manifest::Manifest,
+    scrape::Scraper,
+};
+use indexmap::IndexMap;
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+
+pub(super) async fn handler(file: PathBuf, prune: bool) {
+    let manifest = match Manifest::read(&file) {
+        Ok(manifest) => manifest,
+        Err(reason) => {
+            println!("❌ {reason}");
+            std::process::exit(1);
+        }
+    };
+    let db = db::open();
+    let local: HashSet<String> = db
+        .versions()
+        .expect("Failed to read versions from db")
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    let mut failed = 0;
+
+    // exact local versions need no download page, so a synced repo works offline
+    let scrap = match manifest
+        .versions
+        .iter()
+        .all(|entry| local.contains(&entry.spec))
+    {
+        true => None,
+        false => Some(Scraper::new().await),
+    };
+    // every version the manifest means, with its expected digest
+    let mut wanted: IndexMap<String, Option<String>> = IndexMap::new();
+    for entry in manifest.versions {
+        let resolved = match &scrap {
+            Some(scrap) if !local.contains(&entry.spec) => resolve_remote(scrap, &entry.spec),
+            _ => vec![entry.spec.clone()],
+        };
+        if resolved.is_empty() {
+            failed += 1;
+            continue;
+        }
+        if entry.sha256.is_some() && resolved.len() > 1 {
+            println!(
+                "❌ sha256 of '{}' is ambiguous, it resolves to {} versions",
+                entry.spec,
+                resolved.len()
+            );
+            failed += 1;
+            continue;
+        }
+        for ver in resolved {
+            let sha256 = wanted.entry(ver).or_default();
+            if let Some(pin) = entry.sha256.as_ref() {
+                *sha256 = Some(pin.to_lowercase());
+            }
+        }
+    }
+
+    // add missing
+    let to_download: Vec<String> = wanted
+        .keys()
+        .filter(|ver| !local.contains(*ver))
+        .cloned()
+        .collect();
+    let max_ver_len = to_download.iter().map(|ver| ver.len()).max();
+    let futures: Vec<_> = to_download
+        .iter()
+        .map(|ver| tokio::spawn(download(db.clone(), ver.clone(), max_ver_len.unwrap())))
+        .collect();
+    let added: Vec<String> = futures::future::join_all(futures)
+        .await
+        .into_iter()
+        .filter_map(|r| r.ok()?.ok())
+        .collect();
+    failed += to_download.len() - added.len();
+
+    // verify pins
+    let repo_dir = get_repo_dir(&*db);
+    for (ver, sha256) in wanted.iter() {
+        let Some(expected) = sha256 else {
+            continue;
+        };
+        if !local.contains(ver) && !added.contains(ver) {
+            continue;
+        }
+        match file_info(&repo_dir.join(format!("{ver}.jar"))) {
+            Ok((_, digest)) if digest == *expected => println!("✅ {ver} sha256 verified"),
+            Ok((_, digest)) => {
+                println!("❌ {ver} sha256 mismatch, expected {expected}, got {digest}");
+                failed += 1;
+            }
+            Err(_) => {
+                println!("❌ {ver} cannot be read to verify");
+                failed += 1;
+            }
+        }
+    }
+
+    let mut journal = match Journal::begin(Action::Sync) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            std::process::exit(1);
+        }
+    };
+    added.iter().for_each(|ver| journal.added(ver.clone()));
+    // prune extra
+    let mut removed = vec![];
+    // a failed entry may mean versions that are local already, pruning could remove them
+    if prune && failed > 0 {
+        println!("🛑 Skip pruning, since {failed} failed");
+    }
+    if prune && failed == 0 {
+        let applied = db.get_applied_ver().unwrap_or_default();
+        let repo_dir = Arc::new(repo_dir);
+        let mut extra: Vec<&String> = local
+            .iter()
+            .filter(|ver| !wanted.contains_key(*ver))
+            .collect();
+        extra.sort();
+        for ver in extra {
+            if applied.as_ref() == Some(ver) {
+                println!("🔵 {ver} is applied, keep it");
+                continue;
+            }
+            if is_pinned(&*db, ver) {
+                println!("📌 {ver} is pinned, keep it");
+                continue;
+            }
+            let name = Arc::new(ver.clone());
+            match handle_one_file(db.clone(), repo_dir.clone(), name).await {
+                Ok((record, id)) => {
+                    println!("✅ {ver} removed");
+                    journal.removed(ver.clone(), record);
+                    journal.trashed(ver.clone(), id);
+                    removed.push(ver.clone());
+                }
+                Err(reason) => {
+                    println!("❌ {ver} failed to remove: {reason}");
+                    failed += 1;
+                }
+            }
+        }
+    }
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+    warn_dangling_aliases(&*db, &removed);
+    if !added.is_empty() || !removed.is_empty() {
+        let detail = format!("{} added, {} removed", added.len(), removed.len());
+        let versions = added.iter().chain(removed.iter()).cloned().collect();
+        record_history(
+            &*db,
+            HistoryEvent::new(Action::Sync, versions).with_detail(detail),
+        );
+    }
+    if let Some(scrap) = scrap {
+        // store opt version in db cache
+        let all_opt_vers: Vec<String> = scrap.get_all_opt_vers().iter().cloned().collect();
+        db.set_all_opt_vers(&all_opt_vers)
+            .expect("Failed to insert Optifine version into database");
+    }
+    db.flush().unwrap();
+
+    println!(
+        "👉 {} added / {} removed / {failed} failed",
+        added.len(),
+        removed.len()
+    );
+    if failed > 0 {
+        // for scripts and CI
+        std::process::exit(1);
+    }
+}
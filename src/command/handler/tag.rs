@@ -0,0 +1,104 @@
+use super::util::{record_history, record_journal, resolve_local_one};
+use crate::db::{self, Action, HistoryEvent};
+
+/// Add or remove tags of a local version, or print them if no tag is given
+pub(super) async fn tag(version: String, tags: Vec<String>, remove: bool) {
+    let db = db::open();
+    let Some(ver) = resolve_local_one(&*db, &version, "tagged") else {
+        std::process::exit(1);
+    };
+    let Some(mut record) = db
+        .get_version(&ver)
+        .expect("Failed to read version from db")
+    else {
+        println!("❌ No version '{ver}' in local repo");
+        std::process::exit(1);
+    };
+    if tags.is_empty() {
+        match record.tags.is_empty() {
+            true => println!("🔵 {ver} has no tag"),
+            false => println!("👉 {ver}: {}", record.tags.join(", ")),
+        }
+        return;
+    }
+    let tags: Vec<String> = tags.iter().map(|tag| tag.trim().to_string()).collect();
+    if tags.iter().any(|tag| tag.is_empty()) {
+        println!("❌ A tag cannot be empty");
+        std::process::exit(1);
+    }
+    let before = record.clone();
+    match remove {
+        true => record.tags.retain(|tag| !tags.contains(tag)),
+        false => record.tags.extend(tags),
+    }
+    record.tags.sort();
+    record.tags.dedup();
+    if record.tags == before.tags {
+        println!("🔵 {ver} is unchanged");
+        return;
+    }
+    let detail = match record.tags.is_empty() {
+        true => "no tag".to_string(),
+        false => record.tags.join(", "),
+    };
+    db.insert_version(&ver, &record)
+        .expect("Failed to write version into db");
+    println!("✅ {ver} tagged: {detail}");
+    record_journal(Action::Tag, |journal| journal.changed(ver.clone(), before));
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Tag, vec![ver]).with_detail(detail),
+    );
+    db.flush().unwrap();
+}
+
+/// Set or clear the note of a local version, or print it if no text is given
+pub(super) async fn note(version: String, text: Option<String>, clear: bool) {
+    let db = db::open();
+    let Some(ver) = resolve_local_one(&*db, &version, "noted") else {
+        std::process::exit(1);
+    };
+    let Some(mut record) = db
+        .get_version(&ver)
+        .expect("Failed to read version from db")
+    else {
+        println!("❌ No version '{ver}' in local repo");
+        std::process::exit(1);
+    };
+    let note = match (text, clear) {
+        (_, true) => None,
+        (Some(text), false) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        (Some(_), false) => {
+            println!("❌ A note cannot be empty, use --clear to remove it");
+            std::process::exit(1);
+        }
+        (None, false) => {
+            match record.note {
+                Some(note) => println!("👉 {ver}: {note}"),
+                None => println!("🔵 {ver} has no note"),
+            }
+            return;
+        }
+    };
+    if record.note == note {
+        println!("🔵 {ver} is unchanged");
+        return;
+    }
+    let before = record.clone();
+    record.note = note;
+    db.insert_version(&ver, &record)
+        .expect("Failed to write version into db");
+    match record.note.as_ref() {
+        Some(_) => println!("✅ {ver} noted"),
+        None => println!("✅ {ver} note cleared"),
+    }
+    record_journal(Action::Note, |journal| journal.changed(ver.clone(), before));
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Note, vec![ver]).with_detail(match record.note {
+            Some(note) => note,
+            None => "cleared".to_string(),
+        }),
+    );
+    db.flush().unwrap();
+}
@@ -0,0 +1,198 @@
+use super::{
+    prompt,
+    util::{drop_dangling_aliases, format_size, get_repo_dir, record_history, record_journal},
+};
+use crate::{
+    db::{self, Action, ConfigKey, HistoryEvent, Store},
+    trash::{self, TrashItem},
+};
+use chrono::{Duration, Utc};
+
+/// Days a version stays in trash, unless configured
+const DEFAULT_TRASH_DAYS: u32 = 30;
+
+pub(super) async fn list() {
+    let db = db::open();
+    let items = match trash::items() {
+        Ok(items) => items,
+        Err(err) => {
+            println!("❌ Failed to read trash: {err}");
+            return;
+        }
+    };
+    if items.is_empty() {
+        println!("🔵 Trash is empty");
+        return;
+    }
+    let max_name_len = items.iter().map(|item| item.name().len()).max().unwrap();
+    let mut total = 0;
+    // the newest first, the one `restore` takes
+    for item in items.iter().rev() {
+        let size = item.size();
+        total += size;
+        println!(
+            "   {} {} {} ({})",
+            item.name(),
+            ".".repeat(max_name_len - item.name().len() + 3),
+            item.removed_at_local(),
+            format_size(size)
+        );
+    }
+    let expiry = match trash_days(&*db) {
+        0 => "kept until `opvm trash empty`".to_string(),
+        days => format!("deleted {days} days after removed"),
+    };
+    println!(
+        "👉 {} versions, {} in total, {expiry}",
+        items.len(),
+        format_size(total)
+    );
+}
+
+/// Put the newest trashed version of each name back into repo
+pub(super) async fn restore(names: Vec<String>) {
+    let db = db::open();
+    let mut items = match trash::items() {
+        Ok(items) => items,
+        Err(err) => {
+            println!("❌ Failed to read trash: {err}");
+            return;
+        }
+    };
+    let repo_dir = get_repo_dir(&*db);
+    let mut restored = vec![];
+    for name in names {
+        let Some(i) = items.iter().rposition(|item| item.name() == name) else {
+            println!("❌ No '{name}' in trash");
+            continue;
+        };
+        if db.contains_version(&name).unwrap() {
+            println!("❌ {name} is in local repo already");
+            continue;
+        }
+        let item = items.remove(i);
+        let record = item.record().clone();
+        let path = repo_dir.join(format!("{name}.jar"));
+        let result = std::fs::create_dir_all(&repo_dir).and_then(|_| item.restore_to(&path));
+        if let Err(err) = result {
+            println!("❌ {name} failed to restore: {err}");
+            continue;
+        }
+        db.insert_version(&name, &record)
+            .unwrap_or_else(|_| panic!("Failed to insert Optifine version {name} into datebase"));
+        println!("✅ {name} restored");
+        restored.push(name);
+    }
+    if !restored.is_empty() {
+        record_journal(Action::Restore, |journal| {
+            for name in restored.iter() {
+                journal.restored(name.clone());
+            }
+        });
+        record_history(
+            &*db,
+            HistoryEvent::new(Action::Restore, restored).with_detail("from trash".to_string()),
+        );
+    }
+    db.flush().unwrap();
+}
+
+/// Delete everything in trash for good
+pub(super) async fn empty() {
+    let db = db::open();
+    match trash::items() {
+        Ok(items) if items.is_empty() => println!("🔵 Trash is empty"),
+        Ok(items) => {
+            let question = format!(
+                "🔰 This will delete {} versions in trash for good, keep going?",
+                items.len()
+            );
+            if !prompt::confirm(&question) {
+                return;
+            }
+            let names = items.iter().map(|item| item.name().to_string()).collect();
+            let (count, reclaimed) = remove_items(items);
+            println!(
+                "✅ {count} versions deleted, {} reclaimed",
+                format_size(reclaimed)
+            );
+            drop_gone_aliases(&*db);
+            // undo of an older remove would find its jar gone
+            record_journal(Action::EmptyTrash, |journal| journal.irreversible());
+            record_history(&*db, HistoryEvent::new(Action::EmptyTrash, names));
+            db.flush().unwrap();
+        }
+        Err(err) => println!("❌ Failed to read trash: {err}"),
+    }
+}
+
+/// Delete what has been in trash for longer than configured, run after each command that writes
+pub(super) fn expire() {
+    let db = db::open();
+    let days = trash_days(&*db);
+    drop(db);
+    if days == 0 {
+        return;
+    }
+    let deadline = Utc::now() - Duration::days(days.into());
+    let Ok(expired) = trash::expired(deadline) else {
+        return;
+    };
+    if expired.is_empty() {
+        return;
+    }
+    let names = expired.iter().map(|item| item.name().to_string()).collect();
+    let (count, reclaimed) = remove_items(expired);
+    if count == 0 {
+        return;
+    }
+    println!(
+        "🔵 {count} versions in trash for over {days} days deleted, {} reclaimed",
+        format_size(reclaimed)
+    );
+    let db = db::open();
+    drop_gone_aliases(&*db);
+    // like `trash empty`, nothing before this can be undone
+    record_journal(Action::EmptyTrash, |journal| journal.irreversible());
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::EmptyTrash, names)
+            .with_detail(format!("expired after {days} days")),
+    );
+    db.flush().unwrap();
+}
+
+/// Configured days a version stays in trash, 0 means forever
+fn trash_days(db: &dyn Store) -> u32 {
+    db.get_config(ConfigKey::TrashDays)
+        .unwrap_or_default()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(DEFAULT_TRASH_DAYS)
+}
+
+/// Drop aliases of versions deleted from trash for good
+fn drop_gone_aliases(db: &dyn Store) {
+    let Ok(items) = trash::items() else {
+        return;
+    };
+    let left: Vec<String> = items.iter().map(|item| item.name().to_string()).collect();
+    drop_dangling_aliases(db, &left);
+}
+
+/// Returns how many are deleted and the bytes reclaimed
+fn remove_items(items: Vec<TrashItem>) -> (usize, u64) {
+    let mut count = 0;
+    let mut reclaimed = 0;
+    for item in items {
+        let name = item.name().to_string();
+        let size = item.size();
+        match item.remove() {
+            Ok(_) => {
+                count += 1;
+                reclaimed += size;
+            }
+            Err(err) => println!("❌ Failed to delete {name} in trash: {err}"),
+        }
+    }
+    (count, reclaimed)
+}
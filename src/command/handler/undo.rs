@@ -0,0 +1,279 @@
+use super::util::{get_repo_dir, record_history};
+use crate::{
+    db::{self, Action, ConfigKey, HistoryEvent, StateDocument, Store},
+    home,
+    journal::Journal,
+    trash,
+};
+use std::fs;
+
+pub(super) async fn handler() {
+    let journal = match Journal::last() {
+        Ok(Some(journal)) => journal,
+        Ok(None) => {
+            println!("🔵 Nothing to undo");
+            return;
+        }
+        Err(err) => {
+            println!("❌ Failed to read undo journal: {err}");
+            return;
+        }
+    };
+    let entry = journal.entry();
+    let detail = format!("{} at {}", entry.action, entry.at_local());
+    if entry.irreversible {
+        // anything older would be reverted on top of a state it does not know
+        println!("🛑 {detail} cannot be undone, nor anything before it");
+        std::process::exit(1);
+    }
+    let db = db::open();
+    let failed = revert(&*db, &journal);
+    let mut versions: Vec<String> = entry.removed.iter().map(|(k, _)| k.clone()).collect();
+    versions.extend(entry.added.iter().cloned());
+    versions.extend(entry.loaded.iter().cloned());
+    versions.extend(entry.changed.iter().map(|(k, _)| k.clone()));
+    versions.extend(entry.restored.iter().cloned());
+    if failed > 0 {
+        // keep the entry, so what is left can be reverted by hand
+        println!("🛑 Undo {detail} partly failed");
+        return;
+    }
+    println!("👉 Undid {detail}");
+    record_history(
+        &*db,
+        HistoryEvent::new(Action::Undo, versions).with_detail(detail),
+    );
+    if let Err(err) = journal.remove() {
+        println!("❌ Failed to remove undo journal: {err}");
+    }
+    db.flush().unwrap();
+}
+
+/// Bring the state back to before the entry, returns the number of failed steps
+fn revert(db: &dyn Store, journal: &Journal) -> usize {
+    let entry = journal.entry();
+    let repo_dir = get_repo_dir(db);
+    let mut failed = 0;
+    let mut report = |result: Result<String, String>| match result {
+        Ok(done) => println!("✅ {done}"),
+        Err(reason) => {
+            println!("❌ {reason}");
+            failed += 1;
+        }
+    };
+
+    if entry.state {
+        report(
+            fs::read_to_string(journal.state_path())
+                .ok()
+                .and_then(|content| serde_json::from_str::<StateDocument>(&content).ok())
+                .ok_or_else(|| "Failed to read the state kept in undo journal".to_string())
+                .and_then(|document| {
+                    document
+                        .restore(db)
+                        .map_err(|_| "Failed to write the state into database".to_string())
+                })
+                .map(|_| "state restored".to_string()),
+        );
+    }
+    for name in entry.added.iter() {
+        let path = repo_dir.join(format!("{name}.jar"));
+        report(
+            match path.exists() {
+                true => fs::remove_file(&path),
+                false => Ok(()),
+            }
+            .map_err(|_| format!("Failed to remove '{}'", path.display()))
+            .and_then(|_| {
+                db.remove_version(name)
+                    .map_err(|_| format!("Failed to remove '{name}' from database"))
+            })
+            .map(|_| format!("{name} removed")),
+        );
+    }
+    for name in entry.restored.iter() {
+        let path = repo_dir.join(format!("{name}.jar"));
+        report(
+            db.get_version(name)
+                .ok()
+                .flatten()
+                .ok_or_else(|| format!("'{name}' is no longer in local repo"))
+                .and_then(|record| {
+                    trash::put(name, &path, &record)
+                        .map_err(|_| format!("Failed to move '{name}' back into trash"))
+                })
+                .and_then(|_| {
+                    db.remove_version(name)
+                        .map_err(|_| format!("Failed to remove '{name}' from database"))
+                })
+                .map(|_| format!("{name} moved back into trash")),
+        );
+    }
+    for name in entry.loaded.iter() {
+        report(
+            db.remove_version(name)
+                .map_err(|_| format!("Failed to remove '{name}' from database"))
+                .map(|_| format!("{name} removed from database, its jar is kept")),
+        );
+    }
+    for (name, record) in entry.removed.iter() {
+        let path = repo_dir.join(format!("{name}.jar"));
+        let moved_back = match entry.trashed.get(name) {
+            Some(id) => match trash::get(id) {
+                Ok(Some(item)) => item.restore_to(&path),
+                // brought back by `opvm trash restore` already
+                Ok(None) if db.contains_version(name).unwrap_or_default() => {
+                    report(Ok(format!("{name} restored already")));
+                    continue;
+                }
+                Ok(None) => {
+                    report(Err(format!("'{name}' is no longer in trash")));
+                    continue;
+                }
+                Err(err) => Err(err),
+            },
+            // removed before trash existed, or by `load` whose jar is already gone
+            None => {
+                let stash = journal.jar_path(name);
+                match stash.exists() {
+                    true => home::move_file(&stash, &path),
+                    false => Ok(()),
+                }
+            }
+        };
+        report(
+            moved_back
+                .map_err(|_| format!("Failed to move '{name}' back into repo"))
+                .and_then(|_| {
+                    db.insert_version(name, record)
+                        .map_err(|_| format!("Failed to insert '{name}' into database"))
+                })
+                .map(|_| format!("{name} restored")),
+        );
+    }
+    for (name, record) in entry.changed.iter() {
+        report(
+            db.insert_version(name, record)
+                .map_err(|_| format!("Failed to write '{name}' into database"))
+                .map(|_| format!("{name} changed back")),
+        );
+    }
+    for (alias, previous) in entry.aliases.iter() {
+        report(
+            db.set_alias(alias, previous.as_deref())
+                .map_err(|_| format!("Failed to write alias '{alias}' into database"))
+                .map(|_| match previous {
+                    Some(previous) => format!("{alias} -> {previous} again"),
+                    None => format!("alias {alias} removed"),
+                }),
+        );
+    }
+    if let Some(applied_ver) = entry.applied_ver.as_ref() {
+        let previous = applied_ver.previous.as_deref();
+        report(
+            db.set_applied_ver(previous)
+                .map_err(|_| "Failed to write applied version into database".to_string())
+                .map(|_| match previous {
+                    Some(previous) => format!("applied version set back to {previous}"),
+                    None => "applied version cleared".to_string(),
+                }),
+        );
+    }
+    if let Some(profile) = entry.launcher_profile.as_ref() {
+        report(
+            fs::copy(journal.launcher_profile_path(), profile)
+                .map_err(|_| format!("Failed to restore '{}'", profile.display()))
+                .map(|_| format!("'{}' restored", profile.display())),
+        );
+    }
+    for (key, previous) in entry.config.iter() {
+        let Some(key) = ConfigKey::parse(key) else {
+            report(Err(format!("Unknown config key '{key}'")));
+            continue;
+        };
+        report(
+            db.set_config(key, previous.as_deref())
+                .map_err(|_| format!("Failed to config {}", key.option()))
+                .map(|_| match previous {
+                    Some(previous) => format!("{} set back to '{previous}'", key.option()),
+                    None => format!("{} unset", key.option()),
+                }),
+        );
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::VersionRecord;
+    use chrono::Utc;
+
+    #[test]
+    fn undo_remove() {
+        let _home = home::TestHome::new("undo-remove");
+        let db = db::memory();
+        let repo_dir = get_repo_dir(&*db);
+        fs::create_dir_all(&repo_dir).unwrap();
+        let path = repo_dir.join("1.16.5_HD_U_G8.jar");
+        fs::write(&path, b"jar").unwrap();
+        let record = VersionRecord::at(Utc::now());
+
+        // what `remove` does
+        let mut journal = Journal::begin(Action::Remove).unwrap();
+        let id = trash::put("1.16.5_HD_U_G8", &path, &record).unwrap();
+        journal.removed("1.16.5_HD_U_G8".to_string(), record.clone());
+        journal.trashed("1.16.5_HD_U_G8".to_string(), id.clone());
+
+        assert_eq!(revert(&*db, &journal), 0);
+        assert_eq!(fs::read(&path).unwrap(), b"jar");
+        assert_eq!(db.get_version("1.16.5_HD_U_G8").unwrap(), Some(record));
+        assert!(trash::get(&id).unwrap().is_none());
+        // brought back already, e.g. by `trash restore`
+        assert_eq!(revert(&*db, &journal), 0);
+    }
+
+    #[test]
+    fn undo_apply() {
+        let _home = home::TestHome::new("undo-apply");
+        let db = db::memory();
+        let profile = home::get().join("launcher_profiles.json");
+        fs::write(&profile, "before").unwrap();
+        db.set_applied_ver(Some("1.16.5_HD_U_G7")).unwrap();
+
+        // what `apply` does
+        let mut journal = Journal::begin(Action::Apply).unwrap();
+        journal.backup_launcher_profile(&profile).unwrap();
+        fs::write(&profile, "after").unwrap();
+        journal.applied_ver(db.get_applied_ver().unwrap());
+        db.set_applied_ver(Some("1.16.5_HD_U_G8")).unwrap();
+
+        assert_eq!(revert(&*db, &journal), 0);
+        assert_eq!(fs::read_to_string(&profile).unwrap(), "before");
+        assert_eq!(
+            db.get_applied_ver().unwrap().as_deref(),
+            Some("1.16.5_HD_U_G7")
+        );
+    }
+
+    #[test]
+    fn undo_config() {
+        let _home = home::TestHome::new("undo-config");
+        let db = db::memory();
+        db.set_config(ConfigKey::JavaPath, Some("java")).unwrap();
+
+        // what `config` does
+        let mut journal = Journal::begin(Action::Config).unwrap();
+        journal.config(ConfigKey::JavaPath, Some("java".to_string()));
+        journal.config(ConfigKey::McDir, None);
+        db.set_config(ConfigKey::JavaPath, Some("javaw")).unwrap();
+        db.set_config(ConfigKey::McDir, Some("/mc")).unwrap();
+
+        assert_eq!(revert(&*db, &journal), 0);
+        assert_eq!(
+            db.get_config(ConfigKey::JavaPath).unwrap().as_deref(),
+            Some("java")
+        );
+        assert_eq!(db.get_config(ConfigKey::McDir).unwrap(), None);
+    }
+}
@@ -0,0 +1,184 @@
+use super::{
+    add::download,
+    apply::apply_in,
+    remove::handle_one_file,
+    util::{
+        get_repo_dir, has_up_to_date, is_pinned, local_lines, record_history, warn_dangling_aliases,
+    },
+};
+use crate::{
+    db::{self, Action, HistoryEvent},
+    journal::Journal,
+    scrape::Scraper,
+    version::{MinecraftVersion, OptifineVersion},
+};
+use std::sync::Arc;
+
+/// What to do with one Minecraft version line
+struct Plan {
+    mc_ver: MinecraftVersion,
+    /// The newest version on the download page
+    newest: String,
+    /// Local versions of this line
+    local: Vec<String>,
+    /// Whether `newest` (or a newer one) is local already
+    up_to_date: bool,
+}
+
+impl Plan {
+    /// Local versions older than `newest`, unknown ones are kept
+    fn superseded(&self) -> Vec<String> {
+        let Ok(newest) = self.newest.parse::<OptifineVersion>() else {
+            return vec![];
+        };
+        self.local
+            .iter()
+            .filter(|ver| ver.parse::<OptifineVersion>().is_ok_and(|v| v < newest))
+            .cloned()
+            .collect()
+    }
+}
+
+pub(super) async fn handler(
+    mc_vers: Vec<String>,
+    stable: bool,
+    prune: bool,
+    reapply: bool,
+    force: bool,
+) {
+    let db = db::open();
+
+    let mut lines = local_lines(&*db);
+    if !mc_vers.is_empty() {
+        let mut wanted = vec![];
+        for ver in mc_vers {
+            match ver.parse::<MinecraftVersion>() {
+                Ok(mc_ver) if lines.contains_key(&mc_ver) => wanted.push(mc_ver),
+                Ok(_) => println!("🔵 No local version of Minecraft {ver}"),
+                Err(_) => println!("❌ No such Minecraft version '{ver}'"),
+            }
+        }
+        lines.retain(|mc_ver, _| wanted.contains(mc_ver));
+    }
+    if lines.is_empty() {
+        println!("🔵 Nothing to update");
+        return;
+    }
+    let scrap = Scraper::new().await;
+
+    let mut plans = vec![];
+    // newest line first, like `search`
+    for (mc_ver, local) in lines.into_iter().rev() {
+        let remote = scrap.get_opt_vers(&mc_ver.to_string());
+        let Some(newest) = remote
+            .iter()
+            .find(|ver| !stable || !Scraper::is_preview(ver))
+            .cloned()
+        else {
+            println!("❌ No Optifine version of Minecraft {mc_ver} on the download page");
+            continue;
+        };
+        let up_to_date = has_up_to_date(&local, &newest);
+        plans.push(Plan {
+            mc_ver,
+            newest,
+            local,
+            up_to_date,
+        });
+    }
+
+    let to_download: Vec<&Plan> = plans.iter().filter(|plan| !plan.up_to_date).collect();
+    for plan in plans.iter().filter(|plan| plan.up_to_date) {
+        println!("✅ {} is up to date", plan.mc_ver);
+    }
+    let max_ver_len = to_download.iter().map(|plan| plan.newest.len()).max();
+    let futures: Vec<_> = to_download
+        .iter()
+        .map(|plan| {
+            println!("📦 {}: {} is available", plan.mc_ver, plan.newest);
+            tokio::spawn(download(
+                db.clone(),
+                plan.newest.clone(),
+                max_ver_len.unwrap(),
+            ))
+        })
+        .collect();
+    let downloaded: Vec<String> = futures::future::join_all(futures)
+        .await
+        .into_iter()
+        .filter_map(|r| r.ok()?.ok())
+        .collect();
+    // lines whose newest version is local now
+    let updated: Vec<&Plan> = plans
+        .iter()
+        .filter(|plan| plan.up_to_date || downloaded.contains(&plan.newest))
+        .collect();
+
+    let mut journal = match Journal::begin(Action::Update) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            return;
+        }
+    };
+    downloaded.iter().for_each(|ver| journal.added(ver.clone()));
+    // re-applying goes into the same entry, so one undo reverts the whole update
+    if reapply {
+        let applied = db.get_applied_ver().unwrap_or_default();
+        let stale = updated.iter().find(|plan| {
+            applied
+                .as_ref()
+                .is_some_and(|applied| *applied != plan.newest && plan.local.contains(applied))
+        });
+        if let Some(plan) = stale {
+            apply_in(&*db, &plan.newest, &mut journal).await;
+        }
+    }
+
+    let mut removed = vec![];
+    if prune {
+        let applied = db.get_applied_ver().unwrap_or_default();
+        let repo_dir = Arc::new(get_repo_dir(&*db));
+        for plan in updated.iter() {
+            for ver in plan.superseded() {
+                // the applied one stays, unless it was re-applied above
+                if applied.as_ref() == Some(&ver) {
+                    println!("🔵 {ver} is applied, keep it");
+                    continue;
+                }
+                if !force && is_pinned(&*db, &ver) {
+                    println!("📌 {ver} is pinned, keep it (use --force to remove it)");
+                    continue;
+                }
+                let name = Arc::new(ver.clone());
+                match handle_one_file(db.clone(), repo_dir.clone(), name).await {
+                    Ok((record, id)) => {
+                        println!("✅ {ver} removed");
+                        journal.removed(ver.clone(), record);
+                        journal.trashed(ver.clone(), id);
+                        removed.push(ver);
+                    }
+                    Err(reason) => println!("❌ {ver} failed to remove: {reason}"),
+                }
+            }
+        }
+    }
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+    warn_dangling_aliases(&*db, &removed);
+    if !downloaded.is_empty() || !removed.is_empty() {
+        let detail = format!("{} downloaded, {} removed", downloaded.len(), removed.len());
+        let versions = downloaded.into_iter().chain(removed).collect();
+        record_history(
+            &*db,
+            HistoryEvent::new(Action::Update, versions).with_detail(detail),
+        );
+    }
+
+    // store opt version in db cache
+    let all_opt_vers: Vec<String> = scrap.get_all_opt_vers().iter().cloned().collect();
+    db.set_all_opt_vers(&all_opt_vers)
+        .expect("Failed to insert Optifine version into database");
+    db.flush().unwrap();
+}
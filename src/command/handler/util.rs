@@ -1,55 +1,389 @@
 use super::list::SortBy;
-use crate::db::Tree;
-use std::{cmp::Ordering, collections::HashMap};
+use crate::{
+    db::{Action, ConfigKey, HistoryEvent, Store, VersionRecord},
+    home,
+    index::{destruct_input, parse, purify},
+    journal::Journal,
+    version::{MinecraftVersion, OptifineVersion},
+};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
 
-pub(super) fn get_current_time() -> String {
-    let date = chrono::Local::now();
-    date.format("%Y-%m-%d %H:%M:%S").to_string()
+/// The configured repo dir, or the default one in opvm home
+pub(super) fn get_repo_dir(db: &dyn Store) -> PathBuf {
+    match db.get_config(ConfigKey::RepoDir).unwrap() {
+        Some(repo_dir) => PathBuf::from(repo_dir),
+        None => home::default_repo_dir(),
+    }
+}
+
+/// Print a step of a dry run, what would happen to `what`
+pub(super) fn print_plan(what: &str, steps: &[String]) {
+    println!("📝 {what}");
+    for step in steps {
+        println!("   👉 {step}");
+    }
+}
+
+/// Append an event to the history, the command itself is done anyway
+pub(super) fn record_history(db: &dyn Store, event: HistoryEvent) {
+    if db.append_history(&event).is_err() {
+        println!("❌ Failed to write history");
+    }
+}
+
+/// Write an entry of the undo journal for a command already done
+pub(super) fn record_journal(action: Action, fill: impl FnOnce(&mut Journal)) {
+    let mut journal = match Journal::begin(action) {
+        Ok(journal) => journal,
+        Err(err) => {
+            println!("❌ Failed to create undo journal: {err}");
+            return;
+        }
+    };
+    fill(&mut journal);
+    if let Err(err) = journal.commit() {
+        println!("❌ Failed to write undo journal: {err}");
+    }
+}
+
+/// Local versions by their Minecraft version, versions of no known Minecraft version are left out
+pub(super) fn local_lines(db: &dyn Store) -> BTreeMap<MinecraftVersion, Vec<String>> {
+    let mut lines: BTreeMap<MinecraftVersion, Vec<String>> = BTreeMap::new();
+    for (ver, _) in db.versions().expect("Failed to read versions from db") {
+        if let Some(mc_ver) = MinecraftVersion::of_opt_ver(&ver) {
+            lines.entry(mc_ver).or_default().push(ver);
+        }
+    }
+    lines
+}
+
+/// The newest of some versions, versions that cannot be parsed are the oldest
+pub(super) fn newest_of(vers: &[String]) -> Option<&String> {
+    vers.iter()
+        .max_by_key(|ver| ver.parse::<OptifineVersion>().ok())
+}
+
+/// Whether `ver`, or a newer version, is in `local`
+pub(super) fn has_up_to_date(local: &[String], ver: &str) -> bool {
+    local.iter().any(|v| v == ver)
+        || ver.parse::<OptifineVersion>().is_ok_and(|ver| {
+            local
+                .iter()
+                .filter_map(|v| v.parse::<OptifineVersion>().ok())
+                .any(|v| v > ver)
+        })
 }
 
 pub(super) fn sort_vers(
-    vers: Vec<(String, String)>,
-    cache_db: &Tree,
+    vers: Vec<(String, VersionRecord)>,
+    db: &dyn Store,
     sort_by: SortBy,
-) -> Vec<(String, String)> {
-    let order_from_db = cache_db.get("all_opt_ver").unwrap().unwrap_or_default();
-    let order: HashMap<String, usize> = bincode::deserialize::<Vec<String>>(&order_from_db)
+) -> Vec<(String, VersionRecord)> {
+    let order: HashMap<String, usize> = db
+        .get_all_opt_vers()
         .unwrap_or_default()
         .into_iter()
         .enumerate()
         .map(|(i, s)| (s, i))
         .collect();
+    // versions that can be parsed are ordered by themselves, which works offline,
+    // others (e.g. custom names) go last in the order of the cached download page
+    let parsed: HashMap<String, OptifineVersion> = vers
+        .iter()
+        .filter_map(|(k, _)| Some((k.clone(), k.parse().ok()?)))
+        .collect();
+    let cmp_by_cache = |ka: &String, kb: &String| match (order.get(ka), order.get(kb)) {
+        (Some(va), Some(vb)) => va.cmp(vb),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
     let mut vers = vers;
     match sort_by {
-        SortBy::NameNew => vers.sort_by_key(|(k, _)| order.get(k).copied().unwrap_or(usize::MAX)),
-        SortBy::NameOld => vers.sort_by(|(ka, _), (kb, _)| {
-            match (order.get(ka), order.get(kb)) {
-                (Some(&va), Some(&vb)) => vb.cmp(&va), // reverse ordering
+        SortBy::NameNew => {
+            vers.sort_by(|(ka, _), (kb, _)| match (parsed.get(ka), parsed.get(kb)) {
+                (Some(va), Some(vb)) => vb.cmp(va), // the newer, the former
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => cmp_by_cache(ka, kb),
+            })
+        }
+        SortBy::NameOld => {
+            vers.sort_by(|(ka, _), (kb, _)| match (parsed.get(ka), parsed.get(kb)) {
+                (Some(va), Some(vb)) => va.cmp(vb), // the older, the former
                 (Some(_), None) => Ordering::Less,
                 (None, Some(_)) => Ordering::Greater,
-                (None, None) => Ordering::Equal,
+                (None, None) => cmp_by_cache(kb, ka), // reverse ordering
+            })
+        }
+        // the later, the former
+        SortBy::TimeNew => vers.sort_by_key(|(_, r)| std::cmp::Reverse(r.added_at)),
+        // the later, the latter
+        SortBy::TimeOld => vers.sort_by_key(|(_, r)| r.added_at),
+    }
+    vers
+}
+
+/// The one local version that `version` means, problems are printed.
+///
+/// `version` can be an alias, a Minecraft version with index (a bare one means `[1]`),
+/// an exact version, or a pattern matching exactly one version.
+/// `verb` tells what is done with it, e.g. "applied".
+pub(super) fn resolve_local_one(db: &dyn Store, version: &str, verb: &str) -> Option<String> {
+    if let Some(target) = db
+        .get_alias(version)
+        .expect("Failed to read aliases from db")
+    {
+        // the version may have been removed since
+        if !db.contains_version(&target).unwrap() {
+            println!("❌ Alias '{version}' points at missing version '{target}'");
+            return None;
+        }
+        return Some(target);
+    }
+    let local_vers = db.versions().expect("Failed to read versions from db");
+
+    // a bare Minecraft version means its newest local version, namely `mc_ver[1]`
+    // if there is no such local version, it is treated as a plain pattern
+    let is_local_mc_ver = |ver: &str| match ver.parse::<MinecraftVersion>() {
+        Ok(mc_ver) => local_vers
+            .iter()
+            .any(|(k, _)| MinecraftVersion::of_opt_ver(k).as_ref() == Some(&mc_ver)),
+        Err(_) => false,
+    };
+    let spec = match destruct_input(version) {
+        Some(spec) => Some(spec),
+        None if is_local_mc_ver(version) => Some((version.to_string(), String::new())),
+        None => None,
+    };
+    let found = match spec {
+        Some((mc_ver, index)) => {
+            let (mut resolved, invalid) = resolve_local_index(db, &mc_ver, &index);
+            for (raw, reason) in invalid {
+                println!("❌ {reason} (at index '{raw}', '{mc_ver}[{raw}]')");
             }
-        }),
-        SortBy::TimeNew => vers.sort_by(|(_, va), (_, vb)| {
-            let time_a = chrono::NaiveDateTime::parse_from_str(va, "%Y-%m-%d %H:%M:%S");
-            let time_b = chrono::NaiveDateTime::parse_from_str(vb, "%Y-%m-%d %H:%M:%S");
-            match (time_a, time_b) {
-                (Ok(ta), Ok(tb)) => tb.cmp(&ta), // the larger, the former
-                (Ok(_), Err(_)) => Ordering::Less,
-                (Err(_), Ok(_)) => Ordering::Greater,
-                (Err(_), Err(_)) => Ordering::Equal,
+            if resolved.len() > 1 {
+                println!(
+                    "❌ '{version}' resolves to {} versions, only one can be {verb}",
+                    resolved.len()
+                );
+                return None;
             }
-        }),
-        SortBy::TimeOld => vers.sort_by(|(_, va), (_, vb)| {
-            let time_a = chrono::NaiveDateTime::parse_from_str(va, "%Y-%m-%d %H:%M:%S");
-            let time_b = chrono::NaiveDateTime::parse_from_str(vb, "%Y-%m-%d %H:%M:%S");
-            match (time_a, time_b) {
-                (Ok(ta), Ok(tb)) => ta.cmp(&tb), // the larger, the latter
-                (Ok(_), Err(_)) => Ordering::Less,
-                (Err(_), Ok(_)) => Ordering::Greater,
-                (Err(_), Err(_)) => Ordering::Equal,
+            resolved.pop()
+        }
+        // an exact name wins, otherwise the pattern must match exactly one version
+        None if local_vers.iter().any(|(k, _)| k == version) => Some(version.to_string()),
+        None => {
+            let vers_unsorted: Vec<(String, VersionRecord)> = local_vers
+                .into_iter()
+                .filter(|(k, _)| k.contains(version))
+                .collect();
+            let mut vers = sort_vers(vers_unsorted, db, SortBy::NameNew);
+            if vers.len() > 1 {
+                println!(
+                    "❌ '{version}' is ambiguous, it matches {} versions:",
+                    vers.len()
+                );
+                for (ver, _) in vers {
+                    println!("   👉 {ver}");
+                }
+                return None;
             }
-        }),
+            vers.pop().map(|(ver, _)| ver)
+        }
+    };
+    if found.is_none() {
+        println!("❌ No version matches '{version}' in local repo");
+    }
+    found
+}
+
+/// Resolve `mc_ver[index]` against the locally stored versions of `mc_ver`,
+/// where index 1 is the newest one (ordered by `sort_vers` with `NameNew`).
+/// Returns the resolved versions and the invalid `(raw, reason)` index parts.
+pub(super) fn resolve_local_index(
+    db: &dyn Store,
+    mc_ver: &str,
+    index: &str,
+) -> (Vec<String>, Vec<(String, String)>) {
+    // compare as Minecraft versions if possible, so `1.8[1]` also finds `1.8.0_HD_U_I7`
+    let of_mc_ver: Box<dyn Fn(&str) -> bool> = match mc_ver.parse::<MinecraftVersion>() {
+        Ok(mc_ver) => Box::new(move |k| MinecraftVersion::of_opt_ver(k).as_ref() == Some(&mc_ver)),
+        Err(_) => {
+            let pat = format!("{mc_ver}_");
+            Box::new(move |k| k.starts_with(&pat))
+        }
+    };
+    let vers_unsorted: Vec<(String, VersionRecord)> = db
+        .versions()
+        .expect("Failed to read versions from db")
+        .into_iter()
+        .filter(|(k, _)| of_mc_ver(k))
+        .collect();
+    let vers = sort_vers(vers_unsorted, db, SortBy::NameNew);
+    let (valid_index, invalid_index) = parse(index, vers.len());
+    let resolved = purify(valid_index, vers.len())
+        .into_iter()
+        .map(|i| vers[i - 1].0.clone())
+        .collect();
+    (resolved, invalid_index)
+}
+
+/// The version an alias points to, or the name itself if it is no alias
+pub(super) fn resolve_alias(db: &dyn Store, name: &str) -> String {
+    db.get_alias(name)
+        .expect("Failed to read aliases from db")
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Aliases of each version, in alias order
+pub(super) fn aliases_by_version(db: &dyn Store) -> HashMap<String, Vec<String>> {
+    let mut by_version: HashMap<String, Vec<String>> = HashMap::new();
+    for (alias, ver) in db.aliases().expect("Failed to read aliases from db") {
+        by_version.entry(ver).or_default().push(alias);
+    }
+    by_version
+}
+
+/// Tell about aliases left pointing at versions just removed,
+/// they work again once the versions are brought back by `undo` or `trash restore`
+pub(super) fn warn_dangling_aliases(db: &dyn Store, removed: &[String]) {
+    for (alias, ver) in db.aliases().expect("Failed to read aliases from db") {
+        if removed.contains(&ver) {
+            println!("🔵 Alias '{alias}' now points at removed version '{ver}'");
+        }
+    }
+}
+
+/// Drop aliases of versions that are neither local nor in trash, they can never work again
+pub(super) fn drop_dangling_aliases(db: &dyn Store, trashed: &[String]) {
+    for (alias, ver) in db.aliases().expect("Failed to read aliases from db") {
+        if trashed.contains(&ver) || db.contains_version(&ver).unwrap() {
+            continue;
+        }
+        db.set_alias(&alias, None)
+            .expect("Failed to remove alias in db");
+        println!("🔵 Alias '{alias}' removed, '{ver}' is gone for good");
+    }
+}
+
+/// Whether a local version is pinned, see `opvm pin`
+pub(super) fn is_pinned(db: &dyn Store, ver: &str) -> bool {
+    db.get_version(ver)
+        .expect("Failed to read version from db")
+        .is_some_and(|record| record.pinned)
+}
+
+/// Size units, the largest first
+pub(super) const UNITS: [(&str, u64); 3] = [("G", 1 << 30), ("M", 1 << 20), ("K", 1 << 10)];
+
+/// A size for humans, e.g. `1.5 GiB`
+pub(super) fn format_size(size: u64) -> String {
+    UNITS
+        .iter()
+        .find(|(_, unit)| size >= *unit)
+        .map(|(suffix, unit)| format!("{:.1} {suffix}iB", size as f64 / *unit as f64))
+        .unwrap_or_else(|| format!("{size} B"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use chrono::Utc;
+
+    #[test]
+    fn sort_versions() {
+        let db = db::memory();
+        // names that cannot be parsed follow the download page, the newest first
+        db.set_all_opt_vers(&["custom-new".to_string(), "custom-old".to_string()])
+            .unwrap();
+        let vers: Vec<(String, VersionRecord)> = [
+            ("custom-old", 5),
+            ("1.16.5_HD_U_G8", 1),
+            ("unknown", 4),
+            ("1.20.4_HD_U_I7_pre3", 2),
+            ("custom-new", 6),
+            ("1.20.4_HD_U_I7", 3),
+        ]
+        .into_iter()
+        .map(|(ver, days)| {
+            let record = VersionRecord::at(Utc::now() - chrono::Duration::days(days));
+            (ver.to_string(), record)
+        })
+        .collect();
+        let sorted = |sort_by| -> Vec<String> {
+            sort_vers(vers.clone(), &*db, sort_by)
+                .into_iter()
+                .map(|(ver, _)| ver)
+                .collect()
+        };
+        assert_eq!(
+            sorted(SortBy::NameNew),
+            [
+                "1.20.4_HD_U_I7",
+                "1.20.4_HD_U_I7_pre3",
+                "1.16.5_HD_U_G8",
+                "custom-new",
+                "custom-old",
+                "unknown"
+            ]
+        );
+        assert_eq!(
+            sorted(SortBy::NameOld),
+            [
+                "1.16.5_HD_U_G8",
+                "1.20.4_HD_U_I7_pre3",
+                "1.20.4_HD_U_I7",
+                "unknown",
+                "custom-old",
+                "custom-new"
+            ]
+        );
+        assert_eq!(
+            sorted(SortBy::TimeNew),
+            [
+                "1.16.5_HD_U_G8",
+                "1.20.4_HD_U_I7_pre3",
+                "1.20.4_HD_U_I7",
+                "unknown",
+                "custom-old",
+                "custom-new"
+            ]
+        );
+        let mut time_old = sorted(SortBy::TimeNew);
+        time_old.reverse();
+        assert_eq!(sorted(SortBy::TimeOld), time_old);
+    }
+
+    #[test]
+    fn alias_of_missing_version_resolves_to_none() {
+        let db = db::memory();
+        db.insert_version("1.20.4_HD_U_I7", &VersionRecord::at(Utc::now()))
+            .unwrap();
+        db.set_alias("night", Some("1.20.4_HD_U_I7")).unwrap();
+        db.set_alias("old", Some("1.16.5_HD_U_G8")).unwrap();
+        assert_eq!(
+            resolve_local_one(&*db, "night", "applied").as_deref(),
+            Some("1.20.4_HD_U_I7")
+        );
+        assert_eq!(resolve_local_one(&*db, "old", "applied"), None);
+    }
+
+    #[test]
+    fn aliases_of_versions_gone_are_dropped() {
+        let db = db::memory();
+        db.insert_version("1.20.4_HD_U_I7", &VersionRecord::at(Utc::now()))
+            .unwrap();
+        db.set_alias("local", Some("1.20.4_HD_U_I7")).unwrap();
+        db.set_alias("trashed", Some("1.16.5_HD_U_G8")).unwrap();
+        db.set_alias("gone", Some("1.12.2_HD_U_G5")).unwrap();
+        drop_dangling_aliases(&*db, &["1.16.5_HD_U_G8".to_string()]);
+        let aliases: Vec<String> = db.aliases().unwrap().into_iter().map(|(a, _)| a).collect();
+        assert_eq!(aliases, ["local", "trashed"]);
     }
-    vers
 }
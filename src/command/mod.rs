@@ -1,4 +1,6 @@
+use crate::manifest;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 mod handler;
 mod style;
@@ -11,6 +13,35 @@ pub use handler::command_handler;
 pub struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[arg(
+        long,
+        global = true,
+        env = "OPVM_HOME",
+        help = "opvm data directory (default: opvm/ in the platform data directory)"
+    )]
+    home: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Wait for other running opvm instead of failing"
+    )]
+    wait: bool,
+    #[arg(
+        long,
+        global = true,
+        env = "OPVM_STORE",
+        value_enum,
+        help = "Storage backend (default: file if state.json exists in opvm home, sled otherwise)"
+    )]
+    store: Option<crate::db::Backend>,
+    #[arg(short, long, global = true, help = "Answer yes to every confirmation")]
+    yes: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Never prompt, fail instead (also when stdin is not a terminal)"
+    )]
+    no_input: bool,
 }
 
 #[derive(Subcommand)]
@@ -22,6 +53,7 @@ enum Commands {
             Version can be:\n\
             * Minecraft Version[Index Range]\n  \
               - 1.16.5[1]\n  \
+              - 1.16.5[latest] (= 1.16.5[1])\n  \
               - 1.16.5[1~]\n  \
               - 1.16.5[~2]\n  \
               - 1.16.5[1~2]\n  \
@@ -31,37 +63,76 @@ enum Commands {
               - 1.16.5 (= 1.16.5[1])\n  \
               - 1.20.4 (= 1.20.4[1])\n\
             * Optifine Version\n  \
-              - 1.16.5_HD_U_G8\
+              - 1.16.5_HD_U_G8\n\
+            * A local jar or a URL of a jar (version is read from the jar)\n  \
+              - ./OptiFine_1.16.5_HD_U_G8.jar\n  \
+              - https://example.com/OptiFine_1.16.5_HD_U_G8.jar\
         ")]
         versions: Vec<String>,
+        #[arg(long, help = "Print what would be done without doing it")]
+        dry_run: bool,
     },
     /// Remove Optifine version(s) from local repo
     Remove {
         #[arg(help = "\
             Some local Optifine version patterns\n\
             Pattern can be:\n\
+            * Minecraft Version[Index Range] (index over local versions, 1 = newest)\n  \
+              - 1.16.5[1]\n  \
+              - 1.16.5[3~] (delete all but the two newest)\n\
             * Complete Optifine Version\n\
             * Partial Optifine Version (delete all version that contain this pattern)\n\
             * Minecraft Version (delete all version of that Minecraft version)\n\
+            * An alias (delete the version it points to)\n\
             * Any name\n\
-            * \"\" (empty string) (meaning delete all files)\n\
+            With --exact, --glob or --regex, patterns are taken as such\n\
+            Use --all to delete all files, an empty pattern is rejected\n\
+            Pinned versions are kept unless --force is given\n\
         ")]
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
         patterns: Vec<String>,
+        #[arg(
+            long,
+            group = "mode",
+            help = "Match complete versions (or aliases) only"
+        )]
+        exact: bool,
+        #[arg(
+            long,
+            group = "mode",
+            help = "Match whole versions by globs, `*` for any characters, `?` for one and `[...]` for one in a class"
+        )]
+        glob: bool,
+        #[arg(long, group = "mode", help = "Match versions by regular expressions")]
+        regex: bool,
+        #[arg(
+            short,
+            long,
+            conflicts_with_all = ["exact", "glob", "regex"],
+            help = "Remove every local version"
+        )]
+        all: bool,
+        #[arg(short, long, help = "Also remove pinned versions")]
+        force: bool,
+        #[arg(long, help = "Print what would be done without doing it")]
+        dry_run: bool,
     },
-    // TODO?: Minecraft Version = Minecraft Version[1]
     /// Apply Optifine by opening setting GUI
     Apply {
         #[arg(help = "\
             A local Minecraft version with index, or a pattern\n\
             Minecraft version with index can be:\n\
             * 1.20.4[] = (1.20.4[1])\n\
+            * 1.20.4 = (1.20.4[1])\n\
             * 1.8.9[5]\n\
-            Pattern can be:\n\
+            Pattern can be (must match exactly one version):\n\
             * Any name\n\
-            * Custom name\n\
+            * An alias (see `opvm alias`)\n\
             * An Optifine version\n\
         ")]
         version: String,
+        #[arg(long, help = "Print what would be done without doing it")]
+        dry_run: bool,
     },
     /// Config on opvm
     Config {
@@ -71,12 +142,21 @@ enum Commands {
         java_path: Option<String>,
         #[arg(short, long, help = "File folder to store Optifine files")]
         repo_dir: Option<String>,
+        #[arg(
+            long,
+            help = "Days before a removed version is deleted from trash (default: 30, 0: never)"
+        )]
+        trash_days: Option<String>,
         #[arg(short, long, help = "Validate config fields correctness")]
         test: bool,
     },
     /// List downloaded Optifine versions
     List {
-        #[arg(help = "Empty or a pattern")]
+        #[arg(help = "\
+            Empty, a pattern, an alias, or a Minecraft version with index\n\
+            Minecraft version with index can be:\n\
+            * 1.16.5[1~3] (the three newest local 1.16.5 versions)\n\
+        ")]
         pattern: Option<String>,
         #[arg(short, long, help = "Force to load version order")]
         load_order: bool,
@@ -84,6 +164,54 @@ enum Commands {
         time: bool,
         #[arg(short, long, help = "Version display order")]
         by: Option<handler::ListSortBy>,
+        #[arg(short, long, help = "Group versions by Minecraft version")]
+        group: bool,
+        #[arg(long = "tag", help = "Only versions with this tag (can be repeated)")]
+        tags: Vec<String>,
+    },
+    /// Show the details of a local version
+    Info {
+        #[arg(help = "A local version, an alias, or a Minecraft version with index")]
+        version: String,
+    },
+    /// Pin local versions, so remove, update, sync and gc keep them
+    Pin {
+        #[arg(
+            required = true,
+            help = "Local versions, aliases, or Minecraft versions with index"
+        )]
+        versions: Vec<String>,
+    },
+    /// Unpin local versions
+    Unpin {
+        #[arg(
+            required = true,
+            help = "Local versions, aliases, or Minecraft versions with index"
+        )]
+        versions: Vec<String>,
+    },
+    /// Add or remove tags of a local version, or show them
+    Tag {
+        #[arg(help = "A local version, an alias, or a Minecraft version with index")]
+        version: String,
+        #[arg(help = "Tags to add (default: show the tags)")]
+        tags: Vec<String>,
+        #[arg(short, long, help = "Remove the tags instead")]
+        remove: bool,
+    },
+    /// Write a note on a local version, or show it
+    Note {
+        #[arg(help = "A local version, an alias, or a Minecraft version with index")]
+        version: String,
+        #[arg(help = "The note (default: show the note)")]
+        text: Option<String>,
+        #[arg(short, long, conflicts_with = "text", help = "Remove the note")]
+        clear: bool,
+    },
+    /// Give local versions other names
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommand,
     },
     /// Search for avaliable Optifine versions
     Search {
@@ -91,5 +219,158 @@ enum Commands {
         version: Option<String>,
     },
     /// Load all Optifine files in configured local repo
-    Load,
+    Load {
+        #[arg(long, help = "Print what would be done without doing it")]
+        dry_run: bool,
+    },
+    /// Check the database against the repo and config
+    Doctor {
+        #[arg(long, help = "Repair the problems found")]
+        fix: bool,
+    },
+    /// Export config, version records and cache as a JSON document
+    Export {
+        #[arg(help = "Output file (default: print to stdout)")]
+        file: Option<PathBuf>,
+    },
+    /// Import a document written by `export`
+    Import {
+        #[arg(help = "A file written by `opvm export`")]
+        file: PathBuf,
+        #[arg(
+            long,
+            help = "Drop the current state first, instead of merging into it"
+        )]
+        replace: bool,
+    },
+    /// Download the newest Optifine of each Minecraft version in local repo
+    Update {
+        #[arg(help = "Minecraft versions to update (default: all with a local version)")]
+        versions: Vec<String>,
+        #[arg(short, long, help = "Skip preview versions")]
+        stable: bool,
+        #[arg(short, long, help = "Remove local versions older than the newest one")]
+        prune: bool,
+        #[arg(
+            short,
+            long,
+            help = "Apply the newest one if an older one of the same Minecraft version is applied"
+        )]
+        reapply: bool,
+        #[arg(short, long, help = "Also prune pinned versions")]
+        force: bool,
+    },
+    /// Compare local versions with the newest ones, exit with 1 if any can be updated
+    Outdated {
+        #[arg(help = "Minecraft versions to check (default: all with a local version)")]
+        versions: Vec<String>,
+        #[arg(short, long, help = "Ignore preview versions")]
+        stable: bool,
+    },
+    /// Remove local versions by retention policies, applied and pinned ones are kept
+    Gc {
+        #[arg(
+            short,
+            long,
+            value_name = "N",
+            value_parser = handler::parse_keep,
+            help = "Keep the N newest versions of each Minecraft version"
+        )]
+        keep: Option<usize>,
+        #[arg(long, help = "Drop previews older than a local stable version")]
+        drop_previews: bool,
+        #[arg(
+            long,
+            value_name = "DAYS",
+            help = "Drop versions added more than DAYS days ago"
+        )]
+        max_age: Option<u32>,
+        #[arg(
+            long,
+            value_name = "SIZE",
+            value_parser = handler::parse_size,
+            help = "Drop the earliest added versions until the repo fits in SIZE, e.g. 500M or 1G"
+        )]
+        max_size: Option<u64>,
+        #[arg(short, long, help = "Also remove pinned versions")]
+        force: bool,
+        #[arg(long, help = "Print what would be done without doing it")]
+        dry_run: bool,
+    },
+    /// Bring back or delete versions removed by remove, gc, update and sync
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+    /// Make local repo match a manifest
+    Sync {
+        #[arg(short, long, default_value = manifest::DEFAULT_PATH, help = "Manifest file")]
+        file: PathBuf,
+        #[arg(
+            short,
+            long,
+            help = "Remove local versions not in the manifest, except pinned ones"
+        )]
+        prune: bool,
+    },
+    /// Write a manifest for `sync`
+    Manifest {
+        #[command(subcommand)]
+        command: ManifestCommand,
+    },
+    /// Revert the last add, remove, load, apply, config, update, sync or gc
+    Undo,
+    /// Show what add, remove, load, apply, config, update, sync, undo, pin and unpin did
+    History {
+        #[arg(short, long, help = "Only events of this command")]
+        command: Option<crate::db::Action>,
+        #[arg(short, long, help = "Only events on versions containing this pattern")]
+        version: Option<String>,
+        #[arg(long, help = "Only events on or after this date (YYYY-MM-DD)")]
+        since: Option<chrono::NaiveDate>,
+        #[arg(long, help = "Only events on or before this date (YYYY-MM-DD)")]
+        until: Option<chrono::NaiveDate>,
+        #[arg(short = 'n', long, help = "Show the last N events only")]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AliasCommand {
+    /// Point an alias to a local version, replacing what it pointed to
+    Set {
+        #[arg(help = "The alias, e.g. shaders-night")]
+        alias: String,
+        #[arg(help = "A complete local Optifine version")]
+        version: String,
+    },
+    /// Remove an alias, the version stays
+    Remove { alias: String },
+    /// List all aliases
+    List,
+}
+
+#[derive(Subcommand)]
+enum TrashCommand {
+    /// List versions in trash, the newest first
+    List,
+    /// Put versions in trash back into local repo
+    Restore {
+        #[arg(
+            required = true,
+            help = "Versions in trash, the newest one of a name is taken"
+        )]
+        versions: Vec<String>,
+    },
+    /// Delete everything in trash for good
+    Empty,
+}
+
+#[derive(Subcommand)]
+enum ManifestCommand {
+    /// Write all local versions with their SHA-256 into a manifest
+    Freeze {
+        #[arg(short, long, default_value = manifest::DEFAULT_PATH, help = "Manifest file")]
+        file: PathBuf,
+    },
 }
@@ -0,0 +1,108 @@
+use super::{ConfigKey, Error, Origin, Result, Store, VersionRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The version of the exported document format
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The whole opvm state as a human-readable document, see "Export and Import" in README
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateDocument {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// `mc_dir`, `java_path`, `repo_dir` and `trash_days`
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+    /// Version name to its record
+    #[serde(default)]
+    pub versions: BTreeMap<String, VersionRecord>,
+    /// Alias to version
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cache: CacheDocument,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheDocument {
+    /// The version last applied
+    pub applied_ver: Option<String>,
+    /// All Optifine versions on the download page, newest first
+    #[serde(default)]
+    pub all_opt_ver: Vec<String>,
+}
+
+impl StateDocument {
+    pub fn export(db: &dyn Store) -> Result<Self> {
+        let config = db
+            .all_config()?
+            .into_iter()
+            .map(|(k, v)| (k.as_str().to_string(), v))
+            .collect();
+        Ok(Self {
+            format_version: FORMAT_VERSION,
+            exported_at: Utc::now(),
+            config,
+            versions: db.versions()?.into_iter().collect(),
+            aliases: db.aliases()?.into_iter().collect(),
+            cache: CacheDocument {
+                applied_ver: db.get_applied_ver()?,
+                all_opt_ver: db.get_all_opt_vers()?,
+            },
+        })
+    }
+
+    /// Write the document into the store.
+    ///
+    /// With `replace`, the current state is dropped first. Otherwise the document is merged:
+    /// config entries, aliases and the applied version from the document win,
+    /// versions already in the store are kept as they are.
+    /// Versions written are marked `Imported`. Returns the number of them.
+    pub fn import(self, db: &dyn Store, replace: bool) -> Result<usize> {
+        self.write(db, replace, Some(Origin::Imported))
+    }
+
+    /// Put the store back to the document as it is, e.g. a state kept for undo
+    pub fn restore(self, db: &dyn Store) -> Result<()> {
+        self.write(db, true, None).map(|_| ())
+    }
+
+    fn write(self, db: &dyn Store, replace: bool, origin: Option<Origin>) -> Result<usize> {
+        let config = self
+            .config
+            .into_iter()
+            .map(|(k, v)| match ConfigKey::parse(&k) {
+                Some(key) => Ok((key, v)),
+                None => Err(Error::Format(format!("Unknown config key '{k}'"))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if replace {
+            db.clear()?;
+        }
+        for (key, value) in config {
+            db.set_config(key, Some(&value))?;
+        }
+        let mut imported = 0;
+        for (name, mut record) in self.versions {
+            if !db.contains_version(&name)? {
+                if let Some(origin) = origin {
+                    record.origin = origin;
+                }
+                db.insert_version(&name, &record)?;
+                imported += 1;
+            }
+        }
+        for (alias, version) in self.aliases {
+            db.set_alias(&alias, Some(&version))?;
+        }
+        if let Some(applied_ver) = self.cache.applied_ver {
+            db.set_applied_ver(Some(&applied_ver))?;
+        }
+        if !self.cache.all_opt_ver.is_empty() {
+            db.set_all_opt_vers(&self.cache.all_opt_ver)?;
+        }
+        db.flush()?;
+        Ok(imported)
+    }
+}
@@ -0,0 +1,202 @@
+use super::{ConfigKey, Error, HistoryEvent, Result, Store, VersionRecord};
+use crate::home;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Mutex};
+
+/// The schema of `state.json`
+const SCHEMA_VERSION: u32 = 1;
+
+/// The whole state in one JSON file, written on every change.
+/// Keys are sorted, so the file diffs well and can be checked into a repo.
+pub struct FileStore {
+    state: Mutex<FileState>,
+    /// Off for a store kept in memory only, see `in_memory`
+    persist: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FileState {
+    schema_version: u32,
+    #[serde(default)]
+    config: BTreeMap<String, String>,
+    #[serde(default)]
+    versions: BTreeMap<String, VersionRecord>,
+    /// Alias to version
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    cache: FileCache,
+    /// Oldest first
+    #[serde(default)]
+    history: Vec<HistoryEvent>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FileCache {
+    applied_ver: Option<String>,
+    #[serde(default)]
+    all_opt_ver: Vec<String>,
+}
+
+impl Default for FileState {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            config: BTreeMap::new(),
+            versions: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            cache: FileCache::default(),
+            history: vec![],
+        }
+    }
+}
+
+pub fn path() -> PathBuf {
+    home::get().join("state.json")
+}
+
+impl FileStore {
+    pub fn open() -> Result<Self> {
+        let state = match fs::read_to_string(path()) {
+            Ok(content) => serde_json::from_str::<FileState>(&content)
+                .map_err(|err| Error::Format(format!("Invalid {}: {err}", path().display())))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => FileState::default(),
+            Err(err) => return Err(err.into()),
+        };
+        if state.schema_version > SCHEMA_VERSION {
+            return Err(Error::Format(format!(
+                "{} is in schema {}, this opvm supports up to {SCHEMA_VERSION}",
+                path().display(),
+                state.schema_version
+            )));
+        }
+        Ok(Self {
+            state: Mutex::new(state),
+            persist: true,
+        })
+    }
+
+    /// An empty store that writes nothing down
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            state: Mutex::new(FileState::default()),
+            persist: false,
+        }
+    }
+
+    /// Change the state and write it down
+    fn update<T>(&self, f: impl FnOnce(&mut FileState) -> T) -> Result<T> {
+        let mut state = self.state.lock().unwrap();
+        let result = f(&mut state);
+        if !self.persist {
+            return Ok(result);
+        }
+        let content = serde_json::to_string_pretty(&*state).expect("Failed to serialize state");
+        // write aside then rename, so the file is never half written
+        let tmp = path().with_extension("json.tmp");
+        fs::write(&tmp, content + "\n")?;
+        fs::rename(tmp, path())?;
+        Ok(result)
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&FileState) -> T) -> Result<T> {
+        Ok(f(&self.state.lock().unwrap()))
+    }
+}
+
+impl Store for FileStore {
+    fn get_config(&self, key: ConfigKey) -> Result<Option<String>> {
+        self.read(|state| state.config.get(key.as_str()).cloned())
+    }
+    fn set_config(&self, key: ConfigKey, value: Option<&str>) -> Result<()> {
+        self.update(|state| match value {
+            Some(value) => {
+                state
+                    .config
+                    .insert(key.as_str().to_string(), value.to_string());
+            }
+            None => {
+                state.config.remove(key.as_str());
+            }
+        })
+    }
+
+    fn get_version(&self, name: &str) -> Result<Option<VersionRecord>> {
+        self.read(|state| state.versions.get(name).cloned())
+    }
+    fn insert_version(&self, name: &str, record: &VersionRecord) -> Result<()> {
+        self.update(|state| {
+            state.versions.insert(name.to_string(), record.clone());
+        })
+    }
+    fn remove_version(&self, name: &str) -> Result<Option<VersionRecord>> {
+        self.update(|state| state.versions.remove(name))
+    }
+    fn versions(&self) -> Result<Vec<(String, VersionRecord)>> {
+        self.read(|state| {
+            state
+                .versions
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        })
+    }
+
+    fn get_alias(&self, alias: &str) -> Result<Option<String>> {
+        self.read(|state| state.aliases.get(alias).cloned())
+    }
+    fn set_alias(&self, alias: &str, version: Option<&str>) -> Result<()> {
+        self.update(|state| match version {
+            Some(version) => {
+                state.aliases.insert(alias.to_string(), version.to_string());
+            }
+            None => {
+                state.aliases.remove(alias);
+            }
+        })
+    }
+    fn aliases(&self) -> Result<Vec<(String, String)>> {
+        self.read(|state| {
+            state
+                .aliases
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        })
+    }
+
+    fn get_applied_ver(&self) -> Result<Option<String>> {
+        self.read(|state| state.cache.applied_ver.clone())
+    }
+    fn set_applied_ver(&self, name: Option<&str>) -> Result<()> {
+        self.update(|state| state.cache.applied_ver = name.map(str::to_string))
+    }
+    fn get_all_opt_vers(&self) -> Result<Vec<String>> {
+        self.read(|state| state.cache.all_opt_ver.clone())
+    }
+    fn set_all_opt_vers(&self, vers: &[String]) -> Result<()> {
+        self.update(|state| state.cache.all_opt_ver = vers.to_vec())
+    }
+
+    fn append_history(&self, event: &HistoryEvent) -> Result<()> {
+        self.update(|state| state.history.push(event.clone()))
+    }
+    fn history(&self) -> Result<Vec<HistoryEvent>> {
+        self.read(|state| state.history.clone())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.update(|state| {
+            let history = std::mem::take(&mut state.history);
+            *state = FileState {
+                history,
+                ..FileState::default()
+            };
+        })
+    }
+    fn flush(&self) -> Result<()> {
+        // every change is written already
+        Ok(())
+    }
+}
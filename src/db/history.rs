@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The command that made a history event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Add,
+    Remove,
+    Load,
+    Apply,
+    Config,
+    Update,
+    Sync,
+    Undo,
+    Pin,
+    Unpin,
+    Tag,
+    Note,
+    Gc,
+    Restore,
+    Import,
+    Alias,
+    Doctor,
+    EmptyTrash,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::Add => "add",
+            Action::Remove => "remove",
+            Action::Load => "load",
+            Action::Apply => "apply",
+            Action::Config => "config",
+            Action::Update => "update",
+            Action::Sync => "sync",
+            Action::Undo => "undo",
+            Action::Pin => "pin",
+            Action::Unpin => "unpin",
+            Action::Tag => "tag",
+            Action::Note => "note",
+            Action::Gc => "gc",
+            Action::Restore => "restore",
+            Action::Import => "import",
+            Action::Alias => "alias",
+            Action::Doctor => "doctor",
+            Action::EmptyTrash => "empty-trash",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// An entry of the history, appended by the commands that change the state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    pub at: DateTime<Utc>,
+    pub action: Action,
+    /// The versions affected
+    #[serde(default)]
+    pub versions: Vec<String>,
+    /// Anything else worth to know, e.g. the config entry set
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+impl HistoryEvent {
+    /// An event happened now
+    pub fn new(action: Action, versions: Vec<String>) -> Self {
+        Self {
+            at: Utc::now(),
+            action,
+            versions,
+            detail: None,
+        }
+    }
+    pub fn with_detail(self, detail: String) -> Self {
+        Self {
+            detail: Some(detail),
+            ..self
+        }
+    }
+    pub fn at_local(&self) -> String {
+        super::local_time(self.at)
+    }
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Failed to serialize history event")
+    }
+    pub(super) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
@@ -0,0 +1,109 @@
+use super::{file_info, ivec_to_string, Origin, VersionRecord};
+use crate::{home, version::MinecraftVersion};
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use std::path::PathBuf;
+
+/// The schema the current code reads and writes
+///
+/// * 0: `version` values are local time strings like `2025-01-01 12:00:00`
+/// * 1: `version` values are serialized `VersionRecord`s
+pub(super) const SCHEMA_VERSION: u32 = 1;
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrade the database to `SCHEMA_VERSION` step by step
+pub(super) fn migrate(db: &sled::Db) -> sled::Result<()> {
+    let mut schema_version = match db.get(SCHEMA_VERSION_KEY)? {
+        Some(ivec) => ivec_to_string(&ivec).parse().unwrap_or(0),
+        None => 0,
+    };
+    if schema_version > SCHEMA_VERSION {
+        panic!("Database schema {schema_version} is newer than this opvm supports ({SCHEMA_VERSION}), please upgrade opvm");
+    }
+    while schema_version < SCHEMA_VERSION {
+        match schema_version {
+            0 => migrate_0_to_1(db)?,
+            _ => unreachable!(),
+        }
+        schema_version += 1;
+        db.insert(SCHEMA_VERSION_KEY, schema_version.to_string().as_bytes())?;
+    }
+    db.flush()?;
+    Ok(())
+}
+
+fn migrate_0_to_1(db: &sled::Db) -> sled::Result<()> {
+    let config = db.open_tree("config")?;
+    let version = db.open_tree("version")?;
+    let repo_dir = match config.get("repo_dir")? {
+        Some(ivec) => PathBuf::from(ivec_to_string(&ivec)),
+        None => home::default_repo_dir(),
+    };
+    for entry in version.iter() {
+        let (k, v) = entry?;
+        if VersionRecord::from_bytes(&v).is_some() {
+            continue;
+        }
+        let name = ivec_to_string(&k);
+        // the old value is the local time the version was added
+        let added_at = NaiveDateTime::parse_from_str(&ivec_to_string(&v), "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .and_then(|time| Local.from_local_datetime(&time).earliest())
+            .map(|time| time.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+        let (size, digest) = match file_info(&repo_dir.join(format!("{name}.jar"))) {
+            Ok((size, digest)) => (Some(size), Some(digest)),
+            Err(_) => (None, None),
+        };
+        let record = VersionRecord {
+            added_at,
+            size,
+            digest,
+            source_url: None,
+            mc_ver: MinecraftVersion::of_opt_ver(&name).map(|v| v.to_string()),
+            // the old schema didn't record where a version came from
+            origin: Origin::Loaded,
+            pinned: false,
+            tags: vec![],
+            note: None,
+        };
+        version.insert(k, record.to_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_time_strings_to_records() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let dir = home::TempDir::new("migrate");
+        let repo = dir.path();
+        std::fs::write(repo.join("1.16.5_HD_U_G8.jar"), b"jar").unwrap();
+        let config = db.open_tree("config").unwrap();
+        config
+            .insert("repo_dir", repo.to_string_lossy().as_bytes())
+            .unwrap();
+        let version = db.open_tree("version").unwrap();
+        version
+            .insert("1.16.5_HD_U_G8", "2025-01-01 12:00:00")
+            .unwrap();
+        version.insert("custom", "not a time").unwrap();
+
+        migrate(&db).unwrap();
+        // migrating again is a no-op
+        migrate(&db).unwrap();
+
+        let schema_version = ivec_to_string(&db.get(SCHEMA_VERSION_KEY).unwrap().unwrap());
+        assert_eq!(schema_version, SCHEMA_VERSION.to_string());
+        let get = |name: &str| VersionRecord::from_bytes(&version.get(name).unwrap().unwrap());
+        let record = get("1.16.5_HD_U_G8").unwrap();
+        assert_eq!(record.added_at_local(), "2025-01-01 12:00:00");
+        assert_eq!(record.size, Some(3));
+        assert_eq!(record.mc_ver.as_deref(), Some("1.16.5"));
+        assert_eq!(record.origin, Origin::Loaded);
+        let record = get("custom").unwrap();
+        assert_eq!((record.size, record.mc_ver), (None, None));
+    }
+}
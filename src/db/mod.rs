@@ -0,0 +1,175 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+mod document;
+mod file_store;
+mod history;
+mod migrate;
+mod record;
+mod sled_store;
+
+pub use document::{StateDocument, FORMAT_VERSION};
+pub use history::{Action, HistoryEvent};
+pub use record::{file_info, local_time, Origin, VersionRecord};
+pub use sled_store::ivec_to_string;
+
+/// Where opvm keeps its state, behind the `Store` trait
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// sled database in `db/` of opvm home
+    Sled,
+    /// Human-readable JSON in `state.json` of opvm home
+    File,
+}
+
+/// A handle to the opened store, cheap to clone into tasks
+pub type Database = Arc<dyn Store>;
+
+#[derive(Debug)]
+pub enum Error {
+    Sled(sled::Error),
+    Io(std::io::Error),
+    Format(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Sled(err) => write!(f, "{err}"),
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Format(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl From<sled::Error> for Error {
+    fn from(error: sled::Error) -> Error {
+        Error::Sled(error)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+/// Keys of the config, set by `opvm config`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigKey {
+    McDir,
+    JavaPath,
+    RepoDir,
+    TrashDays,
+}
+
+impl ConfigKey {
+    pub const ALL: [ConfigKey; 4] = [
+        ConfigKey::McDir,
+        ConfigKey::JavaPath,
+        ConfigKey::RepoDir,
+        ConfigKey::TrashDays,
+    ];
+
+    /// The key in the store and in exported documents
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigKey::McDir => "mc_dir",
+            ConfigKey::JavaPath => "java_path",
+            ConfigKey::RepoDir => "repo_dir",
+            ConfigKey::TrashDays => "trash_days",
+        }
+    }
+    /// The name of the option in `opvm config`
+    pub fn option(&self) -> &'static str {
+        match self {
+            ConfigKey::McDir => "minecraft-dir",
+            ConfigKey::JavaPath => "java-path",
+            ConfigKey::RepoDir => "repo-dir",
+            ConfigKey::TrashDays => "trash-days",
+        }
+    }
+    pub fn parse(key: &str) -> Option<Self> {
+        ConfigKey::ALL.into_iter().find(|k| k.as_str() == key)
+    }
+}
+
+/// Typed access to the opvm state: config, version records, aliases, cache and history
+pub trait Store: Send + Sync {
+    fn get_config(&self, key: ConfigKey) -> Result<Option<String>>;
+    /// Set a config entry, `None` unsets it
+    fn set_config(&self, key: ConfigKey, value: Option<&str>) -> Result<()>;
+
+    fn get_version(&self, name: &str) -> Result<Option<VersionRecord>>;
+    fn insert_version(&self, name: &str, record: &VersionRecord) -> Result<()>;
+    fn remove_version(&self, name: &str) -> Result<Option<VersionRecord>>;
+    /// All versions with their records, in name order
+    fn versions(&self) -> Result<Vec<(String, VersionRecord)>>;
+
+    /// The version an alias points to
+    fn get_alias(&self, alias: &str) -> Result<Option<String>>;
+    /// Point an alias to a version, `None` removes the alias
+    fn set_alias(&self, alias: &str, version: Option<&str>) -> Result<()>;
+    /// All aliases with their versions, in alias order
+    fn aliases(&self) -> Result<Vec<(String, String)>>;
+
+    /// The version last applied
+    fn get_applied_ver(&self) -> Result<Option<String>>;
+    fn set_applied_ver(&self, name: Option<&str>) -> Result<()>;
+    /// All Optifine versions on the download page, newest first
+    fn get_all_opt_vers(&self) -> Result<Vec<String>>;
+    fn set_all_opt_vers(&self, vers: &[String]) -> Result<()>;
+
+    fn append_history(&self, event: &HistoryEvent) -> Result<()>;
+    /// All history events, oldest first
+    fn history(&self) -> Result<Vec<HistoryEvent>>;
+
+    /// Drop all state but the history
+    fn clear(&self) -> Result<()>;
+    fn flush(&self) -> Result<()>;
+
+    fn contains_version(&self, name: &str) -> Result<bool> {
+        Ok(self.get_version(name)?.is_some())
+    }
+    fn all_config(&self) -> Result<BTreeMap<ConfigKey, String>> {
+        let mut config = BTreeMap::new();
+        for key in ConfigKey::ALL {
+            if let Some(value) = self.get_config(key)? {
+                config.insert(key, value);
+            }
+        }
+        Ok(config)
+    }
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+/// Choose the backend, `init` of `home` must be called before.
+///
+/// Without a explicit choice, the file backend is used if its state file exists.
+pub fn init(backend: Option<Backend>) {
+    let backend = backend.unwrap_or_else(|| match file_store::path().exists() {
+        true => Backend::File,
+        false => Backend::Sled,
+    });
+    BACKEND.get_or_init(|| backend);
+}
+
+/// Open the store of the chosen backend
+pub fn open() -> Database {
+    match BACKEND.get().copied().unwrap_or(Backend::Sled) {
+        Backend::Sled => Arc::new(sled_store::SledStore::open().expect("Failed to open database")),
+        Backend::File => Arc::new(file_store::FileStore::open().expect("Failed to open database")),
+    }
+}
+
+/// A store in memory only, for tests
+#[cfg(test)]
+pub fn memory() -> Database {
+    Arc::new(file_store::FileStore::in_memory())
+}
@@ -0,0 +1,104 @@
+use crate::version::MinecraftVersion;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, io, path::Path};
+
+/// How a version got into the repo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// Downloaded from optifine.net by `add`
+    Downloaded,
+    /// Found in the repo dir by `load`
+    Loaded,
+    /// Brought in from somewhere else, e.g. a local jar, a URL or an exported state
+    Imported,
+}
+
+/// The value of an entry in the `version` tree, keyed by version name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionRecord {
+    pub added_at: DateTime<Utc>,
+    /// Size of the jar in bytes
+    pub size: Option<u64>,
+    /// SHA-256 of the jar in lowercase hex
+    pub digest: Option<String>,
+    pub source_url: Option<String>,
+    pub mc_ver: Option<String>,
+    pub origin: Origin,
+    /// Pinned versions are kept by `remove`, `update` and `sync` unless forced
+    #[serde(default)]
+    pub pinned: bool,
+    /// Free-form tags, sorted, see `opvm tag`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// A free-form note, see `opvm note`
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl VersionRecord {
+    /// A record added now, with size and digest taken from the jar at `path`
+    pub fn from_file(name: &str, path: &Path, origin: Origin) -> io::Result<Self> {
+        let (size, digest) = file_info(path)?;
+        Ok(Self {
+            added_at: Utc::now(),
+            size: Some(size),
+            digest: Some(digest),
+            source_url: None,
+            mc_ver: MinecraftVersion::of_opt_ver(name).map(|v| v.to_string()),
+            origin,
+            pinned: false,
+            tags: vec![],
+            note: None,
+        })
+    }
+    pub fn with_source_url(self, source_url: String) -> Self {
+        Self {
+            source_url: Some(source_url),
+            ..self
+        }
+    }
+    pub fn added_at_local(&self) -> String {
+        local_time(self.added_at)
+    }
+    /// A record of no jar, loaded at `added_at`
+    #[cfg(test)]
+    pub fn at(added_at: DateTime<Utc>) -> Self {
+        Self {
+            added_at,
+            size: None,
+            digest: None,
+            source_url: None,
+            mc_ver: None,
+            origin: Origin::Loaded,
+            pinned: false,
+            tags: vec![],
+            note: None,
+        }
+    }
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Failed to serialize version record")
+    }
+    pub(super) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+/// A time as shown to users, in local time zone, e.g. `2025-01-01 12:00:00`
+pub fn local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Size and SHA-256 digest of a file
+pub fn file_info(path: &Path) -> io::Result<(u64, String)> {
+    let content = fs::read(path)?;
+    let digest = Sha256::digest(&content)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok((content.len() as u64, digest))
+}
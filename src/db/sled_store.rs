@@ -0,0 +1,156 @@
+use super::{migrate, ConfigKey, HistoryEvent, Result, Store, VersionRecord};
+use crate::{home, lock::is_lock_contention};
+use sled::{IVec, Tree};
+use std::time::Duration;
+
+pub struct SledStore {
+    db: sled::Db,
+    config: Tree,
+    version: Tree,
+    alias: Tree,
+    cache: Tree,
+    history: Tree,
+}
+
+impl SledStore {
+    pub fn open() -> Result<Self> {
+        let db = open_with_retry()?;
+        migrate::migrate(&db)?;
+        Ok(Self {
+            config: db.open_tree("config")?,
+            version: db.open_tree("version")?,
+            alias: db.open_tree("alias")?,
+            cache: db.open_tree("cache")?,
+            history: db.open_tree("history")?,
+            db,
+        })
+    }
+}
+
+/// sled allows only one process at a time, so concurrent readers take turns here
+fn open_with_retry() -> sled::Result<sled::Db> {
+    const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+    const RETRY_TIMES: usize = 100;
+    let mut result = sled::open(home::db_path());
+    for _ in 0..RETRY_TIMES {
+        match result {
+            Err(ref err) if is_lock_contention(err) => {
+                std::thread::sleep(RETRY_INTERVAL);
+                result = sled::open(home::db_path());
+            }
+            _ => break,
+        }
+    }
+    result
+}
+
+pub fn ivec_to_string(ivec: &IVec) -> String {
+    std::str::from_utf8(ivec).unwrap().to_string()
+}
+
+impl Store for SledStore {
+    fn get_config(&self, key: ConfigKey) -> Result<Option<String>> {
+        Ok(self.config.get(key.as_str())?.map(|v| ivec_to_string(&v)))
+    }
+    fn set_config(&self, key: ConfigKey, value: Option<&str>) -> Result<()> {
+        match value {
+            Some(value) => self.config.insert(key.as_str(), value.as_bytes())?,
+            None => self.config.remove(key.as_str())?,
+        };
+        Ok(())
+    }
+
+    fn get_version(&self, name: &str) -> Result<Option<VersionRecord>> {
+        let record = self.version.get(name)?;
+        Ok(record.and_then(|ivec| VersionRecord::from_bytes(&ivec)))
+    }
+    fn insert_version(&self, name: &str, record: &VersionRecord) -> Result<()> {
+        self.version.insert(name, record.to_bytes())?;
+        Ok(())
+    }
+    fn remove_version(&self, name: &str) -> Result<Option<VersionRecord>> {
+        let record = self.version.remove(name)?;
+        Ok(record.and_then(|ivec| VersionRecord::from_bytes(&ivec)))
+    }
+    fn versions(&self) -> Result<Vec<(String, VersionRecord)>> {
+        let mut versions = vec![];
+        for entry in self.version.iter() {
+            let (k, v) = entry?;
+            let name = ivec_to_string(&k);
+            let record = VersionRecord::from_bytes(&v).ok_or_else(|| {
+                super::Error::Format(format!("Unreadable record of version '{name}'"))
+            })?;
+            versions.push((name, record));
+        }
+        Ok(versions)
+    }
+
+    fn get_alias(&self, alias: &str) -> Result<Option<String>> {
+        Ok(self.alias.get(alias)?.map(|v| ivec_to_string(&v)))
+    }
+    fn set_alias(&self, alias: &str, version: Option<&str>) -> Result<()> {
+        match version {
+            Some(version) => self.alias.insert(alias, version.as_bytes())?,
+            None => self.alias.remove(alias)?,
+        };
+        Ok(())
+    }
+    fn aliases(&self) -> Result<Vec<(String, String)>> {
+        let mut aliases = vec![];
+        for entry in self.alias.iter() {
+            let (k, v) = entry?;
+            aliases.push((ivec_to_string(&k), ivec_to_string(&v)));
+        }
+        Ok(aliases)
+    }
+
+    fn get_applied_ver(&self) -> Result<Option<String>> {
+        Ok(self.cache.get("applied_ver")?.map(|v| ivec_to_string(&v)))
+    }
+    fn set_applied_ver(&self, name: Option<&str>) -> Result<()> {
+        match name {
+            Some(name) => self.cache.insert("applied_ver", name.as_bytes())?,
+            None => self.cache.remove("applied_ver")?,
+        };
+        Ok(())
+    }
+    fn get_all_opt_vers(&self) -> Result<Vec<String>> {
+        let vers = self.cache.get("all_opt_ver")?;
+        Ok(vers
+            .and_then(|ivec| bincode::deserialize(&ivec).ok())
+            .unwrap_or_default())
+    }
+    fn set_all_opt_vers(&self, vers: &[String]) -> Result<()> {
+        let serial = bincode::serialize(vers).unwrap();
+        self.cache.insert("all_opt_ver", serial)?;
+        Ok(())
+    }
+
+    fn append_history(&self, event: &HistoryEvent) -> Result<()> {
+        // ids grow monotonically, big endian keeps them sorted in the tree
+        let id = self.db.generate_id()?;
+        self.history.insert(id.to_be_bytes(), event.to_bytes())?;
+        Ok(())
+    }
+    fn history(&self) -> Result<Vec<HistoryEvent>> {
+        let mut events = vec![];
+        for entry in self.history.iter() {
+            let (_, v) = entry?;
+            // skip events written by a newer opvm
+            events.extend(HistoryEvent::from_bytes(&v));
+        }
+        Ok(events)
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.config.clear()?;
+        self.version.clear()?;
+        self.alias.clear()?;
+        self.cache.clear()?;
+        Ok(())
+    }
+    fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::db::ivec_to_string;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+static HOME: OnceLock<PathBuf> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    /// The home of the current test, see `TestHome`
+    static TEST_HOME: std::cell::Cell<Option<&'static Path>> = const { std::cell::Cell::new(None) };
+}
+
+/// Resolve the opvm data directory.
+///
+/// The directory is `home` if given (`--home` or `OPVM_HOME`),
+/// otherwise `opvm/` under the platform data directory.
+pub fn init(home: Option<PathBuf>) {
+    let home = home
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("opvm")))
+        .expect("Failed to find a data directory, please set OPVM_HOME");
+    fs::create_dir_all(&home).expect("Failed to create opvm home directory");
+    HOME.get_or_init(|| home);
+}
+
+/// Move an old working-directory installation into opvm home
+pub fn migrate() {
+    if let Err(err) = migrate_from_cwd(get()) {
+        println!("❌ Failed to migrate the database and repo in current directory: {err}");
+    }
+}
+
+/// Whether `migrate` has an old installation to move.
+///
+/// Only a `.db` that looks like a sled database with `repo/` next to it counts,
+/// a `.db` of another tool is left alone. Nothing moves into a home that has a state already.
+pub fn migration_pending() -> bool {
+    let old_db = Path::new(".db");
+    old_db.join("conf").is_file()
+        && old_db.join("db").is_file()
+        && Path::new("repo").is_dir()
+        && !db_path().exists()
+        && !get().join("state.json").exists()
+}
+
+/// The opvm data directory, `init` must be called before
+pub fn get() -> &'static Path {
+    #[cfg(test)]
+    if let Some(home) = TEST_HOME.get() {
+        return home;
+    }
+    HOME.get().expect("opvm home is not initialized")
+}
+
+pub fn db_path() -> PathBuf {
+    get().join("db")
+}
+
+pub fn default_repo_dir() -> PathBuf {
+    get().join("repo")
+}
+
+pub fn test_resources_dir() -> PathBuf {
+    get().join("test_resources")
+}
+
+pub fn journal_dir() -> PathBuf {
+    get().join("journal")
+}
+
+pub fn trash_dir() -> PathBuf {
+    get().join("trash")
+}
+
+/// Before data directory support, the database was `.db` and the default repo was `repo/`,
+/// both relative to the current working directory. Move them into home once.
+fn migrate_from_cwd(home: &Path) -> io::Result<()> {
+    if !migration_pending() {
+        return Ok(());
+    }
+    let old_db = Path::new(".db");
+    let new_db = home.join("db");
+    let cwd = std::env::current_dir()?;
+    move_dir(old_db, &new_db)?;
+    println!(
+        "🚚 Moved database '{}' into '{}'",
+        cwd.join(old_db).display(),
+        new_db.display()
+    );
+
+    // configured paths were relative to the old working directory
+    let db = sled::open(&new_db).map_err(io::Error::other)?;
+    let config = db.open_tree("config").map_err(io::Error::other)?;
+    for key in ["mc_dir", "repo_dir"] {
+        if let Some(path) = config.get(key).map_err(io::Error::other)? {
+            let path = PathBuf::from(ivec_to_string(&path));
+            if path.is_relative() {
+                let absolute = cwd.join(path).to_string_lossy().to_string();
+                config
+                    .insert(key, absolute.as_bytes())
+                    .map_err(io::Error::other)?;
+            }
+        }
+    }
+    let uses_default_repo = !config.contains_key("repo_dir").map_err(io::Error::other)?;
+    db.flush().map_err(io::Error::other)?;
+    drop(db);
+
+    let old_repo = Path::new("repo");
+    let new_repo = home.join("repo");
+    if uses_default_repo && old_repo.is_dir() && !new_repo.exists() {
+        move_dir(old_repo, &new_repo)?;
+        println!(
+            "🚚 Moved repo '{}' into '{}'",
+            cwd.join(old_repo).display(),
+            new_repo.display()
+        );
+    }
+    Ok(())
+}
+
+/// Rename if possible, otherwise (e.g. across file systems) copy and remove
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_dir(from, to)?;
+    fs::remove_dir_all(from)
+}
+
+/// Rename if possible, otherwise (e.g. across file systems) copy and remove
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// A unique directory under the system temp directory, removed when dropped
+#[cfg(test)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("opvm-test-{}-{count}-{name}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A home of its own for the current test thread, removed when dropped
+#[cfg(test)]
+pub struct TestHome {
+    // removed after the override is dropped
+    _dir: TempDir,
+}
+
+#[cfg(test)]
+impl TestHome {
+    pub fn new(name: &str) -> Self {
+        let dir = TempDir::new(name);
+        let home: &'static Path = Box::leak(dir.path().to_path_buf().into_boxed_path());
+        TEST_HOME.set(Some(home));
+        Self { _dir: dir }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestHome {
+    fn drop(&mut self) {
+        TEST_HOME.set(None);
+    }
+}
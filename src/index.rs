@@ -1,7 +1,7 @@
 use indexmap::IndexSet;
 use regex::Regex;
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum ParsedRange<'a> {
     Single(usize),
     Inclusive(usize, usize),
@@ -11,50 +11,56 @@ pub enum ParsedRange<'a> {
     Invalid(&'a str, String),
 }
 
-pub fn parse(raw_index: &str, boundary: usize) -> (Vec<ParsedRange>, Vec<(String, String)>) {
-    let mut valid_index = Vec::new();
-    let mut invalid_index = Vec::new();
-
+pub fn parse(raw_index: &str, boundary: usize) -> (Vec<ParsedRange<'_>>, Vec<(String, String)>) {
     // if the index is "", namely, user enter `a.b.c[]`, see is as `a.b.c[1]`
     if raw_index.is_empty() {
-        valid_index.push(ParsedRange::Single(1));
-        return (valid_index, invalid_index);
+        return parse("1", boundary);
     }
 
+    let mut valid_index = Vec::new();
+    let mut invalid_index = Vec::new();
+
     // for single value
-    let num_re = Regex::new(r"^\d$").unwrap();
+    let num_re = Regex::new(r"^\d+$").unwrap();
     // for range value
     let range_re = Regex::new(r"^(\d*)~(\d*)$").unwrap();
     let indices: Vec<&str> = raw_index.split('/').filter(|e| !e.is_empty()).collect();
+    // an omitted bound is `None`, a bound too large for `usize` is out of range anyway
+    let to_bound = |raw: &str| match raw {
+        "" => None,
+        raw => Some(raw.parse::<usize>().unwrap_or(usize::MAX)),
+    };
 
     for index in indices {
+        // `latest` is the newest one, namely index 1
+        let index = if index == "latest" { "1" } else { index };
         let result = if num_re.is_match(index) {
-            let num: usize = index.parse().unwrap();
+            let num = to_bound(index).unwrap();
             if num > boundary || num == 0 {
                 ParsedRange::Invalid(index, format!("Range should between 1~{boundary}"))
             } else {
                 ParsedRange::Single(num)
             }
         } else if let Some(captures) = range_re.captures(index) {
-            let first = captures.get(1).unwrap().as_str().parse::<usize>();
-            let second = captures.get(2).unwrap().as_str().parse::<usize>();
+            let first = to_bound(captures.get(1).unwrap().as_str());
+            let second = to_bound(captures.get(2).unwrap().as_str());
             match (first, second) {
-                (Ok(n), Err(_)) | (Err(_), Ok(n)) if n > boundary || n == 0 => {
+                (Some(n), None) | (None, Some(n)) if n > boundary || n == 0 => {
                     ParsedRange::Invalid(index, format!("Range should between 1~{boundary}"))
                 }
-                (Ok(n1), Ok(n2)) if n1 > boundary || n2 > boundary || n1 == 0 || n2 == 0 => {
+                (Some(n1), Some(n2)) if n1 > boundary || n2 > boundary || n1 == 0 || n2 == 0 => {
                     ParsedRange::Invalid(index, format!("Range should between 1~{boundary}"))
                 }
-                (Ok(n1), Ok(n2)) if n1 > n2 => ParsedRange::Invalid(
+                (Some(n1), Some(n2)) if n1 > n2 => ParsedRange::Invalid(
                     index,
                     format!(
                         "The starting index({n1}) must not greater than the ending index({n2})"
                     ),
                 ),
-                (Err(_), Err(_)) => ParsedRange::Full,
-                (Ok(n), Err(_)) => ParsedRange::From(n),
-                (Err(_), Ok(n)) => ParsedRange::To(n),
-                (Ok(n1), Ok(n2)) => ParsedRange::Inclusive(n1, n2),
+                (None, None) => ParsedRange::Full,
+                (Some(n), None) => ParsedRange::From(n),
+                (None, Some(n)) => ParsedRange::To(n),
+                (Some(n1), Some(n2)) => ParsedRange::Inclusive(n1, n2),
             }
         } else {
             ParsedRange::Invalid(index, "Syntax error".to_string())
@@ -85,7 +91,8 @@ pub fn purify(valid_ranges: Vec<ParsedRange>, boundary: usize) -> IndexSet<usize
     result
 }
 pub fn destruct_input(with_index: &str) -> Option<(String, String)> {
-    let re = Regex::new(r"(.+)\[(.*)\]").unwrap();
+    // brackets are not allowed in either part, so `a[1][2]` or `a[1]b` is not an index spec
+    let re = Regex::new(r"^([^\[\]]+)\[([^\[\]]*)\]$").unwrap();
     if re.is_match(with_index) {
         let capture = re.captures(with_index).unwrap();
         let mc_ver = capture.get(1).unwrap().as_str().to_string();
@@ -95,3 +102,132 @@ pub fn destruct_input(with_index: &str) -> Option<(String, String)> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const MAX_BOUNDARY: usize = 64;
+
+    fn arb_range(boundary: usize) -> impl Strategy<Value = ParsedRange<'static>> {
+        prop_oneof![
+            (1..=boundary).prop_map(ParsedRange::Single),
+            (1..=boundary)
+                .prop_flat_map(move |n1| (Just(n1), n1..=boundary))
+                .prop_map(|(n1, n2)| ParsedRange::Inclusive(n1, n2)),
+            (1..=boundary).prop_map(ParsedRange::From),
+            (1..=boundary).prop_map(ParsedRange::To),
+            Just(ParsedRange::Full),
+        ]
+    }
+
+    fn to_spec(range: &ParsedRange) -> String {
+        match range {
+            ParsedRange::Single(n) => format!("{n}"),
+            ParsedRange::Inclusive(n1, n2) => format!("{n1}~{n2}"),
+            ParsedRange::From(n) => format!("{n}~"),
+            ParsedRange::To(n) => format!("~{n}"),
+            ParsedRange::Full => "~".to_string(),
+            ParsedRange::Invalid(raw, _) => raw.to_string(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn parse_never_panics(raw in any::<String>(), boundary in 0..=MAX_BOUNDARY) {
+            let _ = parse(&raw, boundary);
+        }
+
+        #[test]
+        fn parse_spec_like_never_panics(raw in "[0-9~/]{0,12}", boundary in 0..=MAX_BOUNDARY) {
+            let _ = parse(&raw, boundary);
+        }
+
+        #[test]
+        fn parse_round_trips(
+            ranges in (1..=MAX_BOUNDARY).prop_flat_map(|b| {
+                (Just(b), prop::collection::vec(arb_range(b), 1..6))
+            })
+        ) {
+            let (boundary, ranges) = ranges;
+            let spec = ranges.iter().map(to_spec).collect::<Vec<_>>().join("/");
+            let (valid, invalid) = parse(&spec, boundary);
+            prop_assert!(invalid.is_empty(), "{spec} gave {invalid:?}");
+            prop_assert_eq!(valid, ranges);
+        }
+
+        #[test]
+        fn purify_stays_in_boundary(raw in "[0-9~/]{0,12}", boundary in 0..=MAX_BOUNDARY) {
+            let (valid, _) = parse(&raw, boundary);
+            for i in purify(valid, boundary) {
+                prop_assert!((1..=boundary).contains(&i), "{raw} gave {i} over 1~{boundary}");
+            }
+        }
+
+        #[test]
+        fn out_of_boundary_is_invalid(boundary in 0..MAX_BOUNDARY, over in 1..=MAX_BOUNDARY) {
+            let n = boundary + over;
+            for spec in [format!("{n}"), format!("{n}~"), format!("~{n}"), format!("1~{n}")] {
+                let (valid, invalid) = parse(&spec, boundary);
+                prop_assert!(valid.is_empty(), "{spec} should be invalid over 1~{boundary}");
+                prop_assert_eq!(invalid.len(), 1);
+            }
+        }
+
+        #[test]
+        fn empty_index_is_first(boundary in 0..=MAX_BOUNDARY) {
+            prop_assert_eq!(parse("", boundary), parse("1", boundary));
+        }
+
+        #[test]
+        fn full_range_covers_boundary(boundary in 0..=MAX_BOUNDARY) {
+            let (valid, _) = parse("~", boundary);
+            let all: Vec<usize> = purify(valid, boundary).into_iter().collect();
+            prop_assert_eq!(all, (1..=boundary).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn destruct_input_round_trips(mc_ver in "[^\\[\\]]{1,16}", index in "[^\\[\\]]{0,16}") {
+            let spec = format!("{mc_ver}[{index}]");
+            prop_assert_eq!(destruct_input(&spec), Some((mc_ver, index)));
+        }
+
+        #[test]
+        fn destruct_input_never_panics(raw in any::<String>()) {
+            let _ = destruct_input(&raw);
+        }
+    }
+
+    #[test]
+    fn large_index_is_out_of_range() {
+        let (valid, invalid) = parse("99999999999999999999999~", 5);
+        assert!(valid.is_empty());
+        assert_eq!(invalid.len(), 1);
+    }
+
+    #[test]
+    fn multi_digit_index() {
+        let (valid, invalid) = parse("12/10~11", 12);
+        assert!(invalid.is_empty());
+        assert_eq!(
+            valid,
+            vec![ParsedRange::Single(12), ParsedRange::Inclusive(10, 11)]
+        );
+    }
+
+    #[test]
+    fn latest_is_first() {
+        assert_eq!(parse("latest", 3), parse("1", 3));
+        assert_eq!(parse("latest/3", 3), parse("1/3", 3));
+        assert_eq!(parse("latest", 0).1.len(), 1);
+    }
+
+    #[test]
+    fn destruct_input_rejects_trailing_or_nested_brackets() {
+        assert_eq!(destruct_input("1.16.5[1]x"), None);
+        assert_eq!(destruct_input("1.16.5[1][2]"), None);
+        assert_eq!(destruct_input("[1]"), None);
+        assert_eq!(destruct_input("1.16.5_HD_U_G8"), None);
+    }
+}
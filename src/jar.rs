@@ -0,0 +1,79 @@
+use regex::{bytes, Regex};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// Find the Optifine version of a jar, e.g. `1.16.5_HD_U_G8`.
+///
+/// The version is a constant in `Config.class` (`net/optifine/Config.class` in newer builds),
+/// the file name is not trusted, since any jar can be renamed.
+pub fn identify(content: &[u8]) -> Result<String, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(content)).map_err(|_| "not a zip file".to_string())?;
+    let version_re = bytes::Regex::new(r"OptiFine_(\d+\.\d+(?:\.\d+)?_HD\w*)").unwrap();
+    for i in 0..archive.len() {
+        let Ok(mut file) = archive.by_index(i) else {
+            continue;
+        };
+        if file.name() != "Config.class" && !file.name().ends_with("/Config.class") {
+            continue;
+        }
+        let mut class = vec![];
+        if file.read_to_end(&mut class).is_err() {
+            continue;
+        }
+        if let Some(version) = version_re.captures(&class).and_then(|c| c.get(1)) {
+            return Ok(String::from_utf8_lossy(version.as_bytes()).to_string());
+        }
+    }
+    Err("not an Optifine jar".to_string())
+}
+
+/// The version in a download file name, like `OptiFine_1.16.5_HD_U_G8.jar`
+/// or `preview_OptiFine_1.20.4_HD_U_I8_pre1.jar`
+pub fn version_of_file_name(file_name: &str) -> Option<String> {
+    let name_re = Regex::new(r"^(?:preview_)?OptiFine_(.+?)(?:\.jar)?$").unwrap();
+    let captures = name_re.captures(file_name)?;
+    Some(captures.get(1)?.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    fn jar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn version_from_config_class() {
+        let content = jar(&[(
+            "net/optifine/Config.class",
+            b"\xca\xfe\xba\xbe\x01\x00\x17OptiFine_1.16.5_HD_U_G8\x01\x00\x061.16.5",
+        )]);
+        assert_eq!(identify(&content), Ok("1.16.5_HD_U_G8".to_string()));
+        let content = jar(&[("Config.class", b"\x01\x00\x15OptiFine_1.8.9_HD_U_M5\x01")]);
+        assert_eq!(identify(&content), Ok("1.8.9_HD_U_M5".to_string()));
+    }
+
+    #[test]
+    fn file_name_is_not_trusted() {
+        let content = jar(&[("notes.txt", b"hello")]);
+        assert!(identify(&content).is_err());
+        let content = jar(&[("Config.class", b"\x01\x00\x061.16.5\x01")]);
+        assert!(identify(&content).is_err());
+        assert!(identify(b"not a zip").is_err());
+        assert_eq!(
+            version_of_file_name("preview_OptiFine_1.20.4_HD_U_I8_pre1.jar"),
+            Some("1.20.4_HD_U_I8_pre1".to_string())
+        );
+    }
+}
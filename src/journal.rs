@@ -0,0 +1,250 @@
+use crate::{
+    db::{local_time, Action, ConfigKey, StateDocument, Store, VersionRecord},
+    home,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Older entries are dropped, along with what is kept in them
+const MAX_ENTRIES: usize = 10;
+const ENTRY_FILE: &str = "entry.json";
+const PROFILE_FILE: &str = "launcher_profiles.json";
+const STATE_FILE: &str = "state.json";
+
+/// The before-state of one command, enough to revert it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub at: DateTime<Utc>,
+    pub action: Action,
+    /// Versions removed with their records
+    #[serde(default)]
+    pub removed: Vec<(String, VersionRecord)>,
+    /// Trash items of the jars of removed versions, by version.
+    /// Jars of versions removed before trash existed are kept in the entry dir.
+    #[serde(default)]
+    pub trashed: BTreeMap<String, String>,
+    /// Versions added, with jar and entry
+    #[serde(default)]
+    pub added: Vec<String>,
+    /// Entries inserted for jars already in repo
+    #[serde(default)]
+    pub loaded: Vec<String>,
+    /// Records before, of versions changed in place, e.g. pinned or tagged
+    #[serde(default)]
+    pub changed: Vec<(String, VersionRecord)>,
+    /// Versions brought back from trash
+    #[serde(default)]
+    pub restored: Vec<String>,
+    /// Aliases before, if they were changed
+    #[serde(default)]
+    pub aliases: BTreeMap<String, Option<String>>,
+    /// The applied version before, if it was changed
+    #[serde(default)]
+    pub applied_ver: Option<AppliedVer>,
+    /// Config entries before, by db key, if they were changed
+    #[serde(default)]
+    pub config: BTreeMap<String, Option<String>>,
+    /// Where the launcher profile backed up in the entry dir belongs
+    #[serde(default)]
+    pub launcher_profile: Option<PathBuf>,
+    /// Whether the whole state before is kept in the entry dir, for commands that rewrite it
+    #[serde(default)]
+    pub state: bool,
+    /// The command cannot be reverted, e.g. trash was emptied, so undo stops here
+    #[serde(default)]
+    pub irreversible: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedVer {
+    pub previous: Option<String>,
+}
+
+impl JournalEntry {
+    fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+            && self.added.is_empty()
+            && self.loaded.is_empty()
+            && self.changed.is_empty()
+            && self.restored.is_empty()
+            && self.aliases.is_empty()
+            && self.applied_ver.is_none()
+            && self.config.is_empty()
+            && self.launcher_profile.is_none()
+            && !self.state
+            && !self.irreversible
+    }
+    pub fn at_local(&self) -> String {
+        local_time(self.at)
+    }
+}
+
+/// An entry of the undo journal, a dir under `journal/` of opvm home
+pub struct Journal {
+    dir: PathBuf,
+    entry: JournalEntry,
+}
+
+impl Journal {
+    /// Start an entry, its dir is created right away so jars can be moved in
+    pub fn begin(action: Action) -> io::Result<Self> {
+        let at = Utc::now();
+        // names sort by time
+        let dir = home::journal_dir().join(at.format("%Y%m%d%H%M%S%6f").to_string());
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            entry: JournalEntry {
+                at,
+                action,
+                removed: vec![],
+                trashed: BTreeMap::new(),
+                added: vec![],
+                loaded: vec![],
+                changed: vec![],
+                restored: vec![],
+                aliases: BTreeMap::new(),
+                applied_ver: None,
+                config: BTreeMap::new(),
+                launcher_profile: None,
+                state: false,
+                irreversible: false,
+            },
+        })
+    }
+
+    /// The newest entry, if any
+    pub fn last() -> io::Result<Option<Self>> {
+        let mut dirs = entry_dirs()?;
+        while let Some(dir) = dirs.pop() {
+            // a dir without entry is left by a command that did not finish
+            let Ok(content) = fs::read_to_string(dir.join(ENTRY_FILE)) else {
+                continue;
+            };
+            let entry = serde_json::from_str(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            return Ok(Some(Self { dir, entry }));
+        }
+        Ok(None)
+    }
+
+    pub fn entry(&self) -> &JournalEntry {
+        &self.entry
+    }
+
+    /// Where the jar of a removed version was kept, before trash existed
+    pub fn jar_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.jar"))
+    }
+
+    /// Where the launcher profile is backed up
+    pub fn launcher_profile_path(&self) -> PathBuf {
+        self.dir.join(PROFILE_FILE)
+    }
+
+    /// A version was removed, see `trashed` for its jar
+    pub fn removed(&mut self, name: String, record: VersionRecord) {
+        self.entry.removed.push((name, record));
+    }
+
+    /// The jar of a removed version was moved into trash
+    pub fn trashed(&mut self, name: String, id: String) {
+        self.entry.trashed.insert(name, id);
+    }
+
+    pub fn added(&mut self, name: String) {
+        self.entry.added.push(name);
+    }
+
+    pub fn loaded(&mut self, name: String) {
+        self.entry.loaded.push(name);
+    }
+
+    /// The record of a version before it was changed in place
+    pub fn changed(&mut self, name: String, record: VersionRecord) {
+        self.entry.changed.push((name, record));
+    }
+
+    pub fn restored(&mut self, name: String) {
+        self.entry.restored.push(name);
+    }
+
+    pub fn alias(&mut self, alias: String, previous: Option<String>) {
+        self.entry.aliases.insert(alias, previous);
+    }
+
+    pub fn irreversible(&mut self) {
+        self.entry.irreversible = true;
+    }
+
+    pub fn applied_ver(&mut self, previous: Option<String>) {
+        self.entry.applied_ver = Some(AppliedVer { previous });
+    }
+
+    pub fn config(&mut self, key: ConfigKey, previous: Option<String>) {
+        self.entry.config.insert(key.as_str().to_string(), previous);
+    }
+
+    pub fn backup_launcher_profile(&mut self, path: &Path) -> io::Result<()> {
+        fs::copy(path, self.launcher_profile_path())?;
+        self.entry.launcher_profile = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Where the whole state is backed up
+    pub fn state_path(&self) -> PathBuf {
+        self.dir.join(STATE_FILE)
+    }
+
+    pub fn backup_state(&mut self, db: &dyn Store) -> io::Result<()> {
+        let document =
+            StateDocument::export(db).map_err(|err| io::Error::other(err.to_string()))?;
+        let content = serde_json::to_string_pretty(&document).expect("Failed to serialize state");
+        fs::write(self.state_path(), content + "\n")?;
+        self.entry.state = true;
+        Ok(())
+    }
+
+    /// Write the entry down and drop the old ones, an entry with nothing to undo is discarded
+    pub fn commit(self) -> io::Result<()> {
+        if self.entry.is_empty() {
+            return self.remove();
+        }
+        let content =
+            serde_json::to_string_pretty(&self.entry).expect("Failed to serialize journal");
+        fs::write(self.dir.join(ENTRY_FILE), content + "\n")?;
+        let dirs = entry_dirs()?;
+        for dir in dirs.iter().take(dirs.len().saturating_sub(MAX_ENTRIES)) {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the entry with everything kept in it
+    pub fn remove(self) -> io::Result<()> {
+        fs::remove_dir_all(self.dir)
+    }
+}
+
+/// Entry dirs, oldest first
+fn entry_dirs() -> io::Result<Vec<PathBuf>> {
+    let mut dirs = vec![];
+    let readdir = match fs::read_dir(home::journal_dir()) {
+        Ok(readdir) => readdir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(dirs),
+        Err(err) => return Err(err),
+    };
+    for entry in readdir {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
@@ -0,0 +1,93 @@
+use crate::home;
+use std::{
+    fs::{self, File, TryLockError},
+    io,
+    path::PathBuf,
+};
+
+/// How a command uses the database and repo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Only reads, may run along with other readers
+    Shared,
+    /// Modifies state, must run alone
+    Exclusive,
+}
+
+/// An advisory lock on the opvm home, released on drop
+pub struct Lock {
+    file: File,
+    mode: LockMode,
+}
+
+impl Lock {
+    /// Take the lock of opvm home, or fail if another opvm holds it and `wait` is false
+    pub fn acquire(mode: LockMode, wait: bool) -> Result<Self, String> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path())
+            .map_err(|err| format!("🛑 Failed to open lock file: {err}"))?;
+        let try_lock = match mode {
+            LockMode::Shared => file.try_lock_shared(),
+            LockMode::Exclusive => file.try_lock(),
+        };
+        match try_lock {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if wait => {
+                println!("⏳ Waiting for another opvm{} to finish...", pid_note());
+                let lock = match mode {
+                    LockMode::Shared => file.lock_shared(),
+                    LockMode::Exclusive => file.lock(),
+                };
+                lock.map_err(|err| format!("🛑 Failed to lock opvm home: {err}"))?;
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(format!(
+                    "🛑 Another opvm{} is running, retry later or use --wait",
+                    pid_note()
+                ));
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(format!("🛑 Failed to lock opvm home: {err}"));
+            }
+        }
+        if mode == LockMode::Exclusive {
+            let _ = fs::write(pid_path(), std::process::id().to_string());
+        }
+        Ok(Self { file, mode })
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if self.mode == LockMode::Exclusive {
+            let _ = fs::remove_file(pid_path());
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+fn lock_path() -> PathBuf {
+    home::get().join("lock")
+}
+
+/// The pid of the exclusive holder is kept aside, since a locked file may not be readable
+fn pid_path() -> PathBuf {
+    home::get().join("lock.pid")
+}
+
+fn pid_note() -> String {
+    let pid = fs::read_to_string(pid_path())
+        .ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok());
+    pid.map(|pid| format!(" (pid {pid})")).unwrap_or_default()
+}
+
+/// Whether an error from opening sled is caused by another process holding the database
+pub fn is_lock_contention(err: &sled::Error) -> bool {
+    matches!(err, sled::Error::Io(err) if err.kind() == io::ErrorKind::WouldBlock
+        || err.to_string().contains("could not acquire lock"))
+}
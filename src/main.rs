@@ -2,8 +2,15 @@ use clap::Parser;
 
 mod command;
 mod db;
+mod home;
 mod index;
+mod jar;
+mod journal;
+mod lock;
+mod manifest;
 mod scrape;
+mod trash;
+mod version;
 
 #[tokio::main]
 async fn main() {
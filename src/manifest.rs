@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// The manifest looked for in the current directory
+pub const DEFAULT_PATH: &str = "opvm.toml";
+
+const HEADER: &str = "\
+# opvm manifest, run `opvm sync` to make local repo match it
+# spec: an Optifine version, or a Minecraft version with index like `1.20.4[1]`
+# sha256: optional, the expected SHA-256 of the jar
+";
+
+/// The versions a repo should have, see "Manifest and Sync" in README
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, rename = "version")]
+    pub versions: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// A version spec `opvm add` takes, but no local jar or URL
+    pub spec: String,
+    /// SHA-256 of the jar in lowercase hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read '{}': {err}", path.display()))?;
+        toml::from_str(&content).map_err(|err| format!("Invalid '{}': {err}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).expect("Failed to serialize manifest");
+        fs::write(path, format!("{HEADER}\n{content}"))
+            .map_err(|err| format!("Failed to write '{}': {err}", path.display()))
+    }
+}
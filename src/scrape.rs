@@ -1,3 +1,4 @@
+use crate::version::{MinecraftVersion, OptifineVersion};
 use indexmap::{IndexMap, IndexSet};
 use regex::Regex;
 use scraper::{Html, Selector};
@@ -108,10 +109,15 @@ impl Scraper {
     pub fn get_opt_vers(&self, mc_ver: &str) -> &[String] {
         if self.opt_ver.borrow().get(mc_ver).is_none() {
             let mc_ver_head_pat = format!("{mc_ver}_");
+            let mc_ver_parsed = mc_ver.parse::<MinecraftVersion>().ok();
             let result = self
                 .get_all_opt_vers()
                 .iter()
-                .filter(|s| s[..].starts_with(&mc_ver_head_pat))
+                .filter(|s| match &mc_ver_parsed {
+                    // `1.8` and `1.8.0` are the same Minecraft version
+                    Some(mc_ver) => MinecraftVersion::of_opt_ver(s).as_ref() == Some(mc_ver),
+                    None => s[..].starts_with(&mc_ver_head_pat),
+                })
                 .map(|s| s.to_string())
                 .collect();
             self.opt_ver.borrow_mut().insert(mc_ver.to_string(), result);
@@ -119,7 +125,16 @@ impl Scraper {
         unsafe { (*self.opt_ver.as_ptr()).get(mc_ver).unwrap() }
     }
     pub fn test_mc_ver(&self, mc_ver: &str) -> bool {
-        self.get_mc_vers().get(mc_ver).is_some()
+        if self.get_mc_vers().get(mc_ver).is_some() {
+            return true;
+        }
+        match mc_ver.parse::<MinecraftVersion>() {
+            Ok(mc_ver) => self
+                .get_mc_vers()
+                .iter()
+                .any(|e| e.parse::<MinecraftVersion>().as_ref() == Ok(&mc_ver)),
+            Err(_) => false,
+        }
     }
     pub fn test_opt_ver(&self, opt_ver: &str) -> bool {
         self.get_all_opt_vers().get(opt_ver).is_some()
@@ -127,15 +142,25 @@ impl Scraper {
 }
 
 impl Scraper {
-    async fn get_download_stream(opt_ver: &str) -> Result<String, ScrapeError> {
-        let url = format!(
+    /// Whether `opt_ver` is a preview build, guessed by its name if it cannot be parsed
+    pub fn is_preview(opt_ver: &str) -> bool {
+        opt_ver
+            .parse::<OptifineVersion>()
+            .map_or(opt_ver.contains("pre"), |v| v.is_preview())
+    }
+    /// The optifine.net page that hands out the download link of `opt_ver`
+    pub fn download_page_url(opt_ver: &str) -> String {
+        format!(
             "https://optifine.net/adloadx?f={file_header}OptiFine_{opt_ver}.jar",
-            file_header = if opt_ver.contains("pre") {
+            file_header = if Self::is_preview(opt_ver) {
                 "preview_"
             } else {
                 ""
             }
-        );
+        )
+    }
+    async fn get_download_stream(opt_ver: &str) -> Result<String, ScrapeError> {
+        let url = Self::download_page_url(opt_ver);
         let html_text = reqwest::get(url).await?.text().await?;
         let dom = Html::parse_document(&html_text);
         const SELECTOR_PATTERN: &str = "table.tableDownload span#Download a";
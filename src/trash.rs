@@ -0,0 +1,185 @@
+use crate::{
+    db::{local_time, VersionRecord},
+    home,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const ITEM_FILE: &str = "item.json";
+
+/// What is known of a trashed version, besides its jar
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemInfo {
+    name: String,
+    record: VersionRecord,
+    removed_at: DateTime<Utc>,
+}
+
+/// A removed version in trash, a dir under `trash/` of opvm home holding its jar
+pub struct TrashItem {
+    dir: PathBuf,
+    info: ItemInfo,
+}
+
+impl TrashItem {
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+    pub fn record(&self) -> &VersionRecord {
+        &self.info.record
+    }
+    pub fn removed_at(&self) -> DateTime<Utc> {
+        self.info.removed_at
+    }
+    pub fn removed_at_local(&self) -> String {
+        local_time(self.info.removed_at)
+    }
+    /// Size of the jar in bytes
+    pub fn size(&self) -> u64 {
+        fs::metadata(self.jar_path()).map_or(0, |meta| meta.len())
+    }
+    fn jar_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.jar", self.info.name))
+    }
+
+    /// Move the jar to `path` and drop the item
+    pub fn restore_to(self, path: &Path) -> io::Result<()> {
+        home::move_file(&self.jar_path(), path)?;
+        fs::remove_dir_all(self.dir)
+    }
+
+    /// Delete the item with its jar for good
+    pub fn remove(self) -> io::Result<()> {
+        fs::remove_dir_all(self.dir)
+    }
+}
+
+/// Move the jar of a removed version into trash, returns the id of the item, unique in trash
+pub fn put(name: &str, jar: &Path, record: &VersionRecord) -> io::Result<String> {
+    let removed_at = Utc::now();
+    let id = format!("{}_{name}", removed_at.format("%Y%m%d%H%M%S%6f"));
+    let dir = home::trash_dir().join(&id);
+    fs::create_dir_all(&dir)?;
+    if let Err(err) = home::move_file(jar, &dir.join(format!("{name}.jar"))) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(err);
+    }
+    let info = ItemInfo {
+        name: name.to_string(),
+        record: record.clone(),
+        removed_at,
+    };
+    let content = serde_json::to_string_pretty(&info).expect("Failed to serialize trash item");
+    fs::write(dir.join(ITEM_FILE), content + "\n")?;
+    Ok(id)
+}
+
+/// The item of an id, if it is still in trash
+pub fn get(id: &str) -> io::Result<Option<TrashItem>> {
+    let dir = home::trash_dir().join(id);
+    match dir.is_dir() {
+        true => read_item(dir),
+        false => Ok(None),
+    }
+}
+
+/// All items, oldest first
+pub fn items() -> io::Result<Vec<TrashItem>> {
+    let readdir = match fs::read_dir(home::trash_dir()) {
+        Ok(readdir) => readdir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let mut dirs = vec![];
+    for entry in readdir {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    let mut items = vec![];
+    for dir in dirs {
+        items.extend(read_item(dir)?);
+    }
+    Ok(items)
+}
+
+/// Items removed before `deadline`, oldest first
+pub fn expired(deadline: DateTime<Utc>) -> io::Result<Vec<TrashItem>> {
+    let mut items = items()?;
+    items.retain(|item| item.removed_at() < deadline);
+    Ok(items)
+}
+
+fn read_item(dir: PathBuf) -> io::Result<Option<TrashItem>> {
+    // a dir without item is left by a command that did not finish
+    let Ok(content) = fs::read_to_string(dir.join(ITEM_FILE)) else {
+        return Ok(None);
+    };
+    let info = serde_json::from_str(&content)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(Some(TrashItem { dir, info }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    /// A jar of `content` in home, to be trashed
+    fn jar(name: &str, content: &[u8]) -> PathBuf {
+        let path = home::get().join(format!("{name}.jar"));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn put_and_restore() {
+        let _home = home::TestHome::new("trash-restore");
+        let path = jar("1.16.5_HD_U_G8", b"jar");
+        let record = VersionRecord::at(Utc::now());
+        let id = put("1.16.5_HD_U_G8", &path, &record).unwrap();
+        assert!(!path.exists());
+
+        let item = get(&id).unwrap().unwrap();
+        assert_eq!(item.name(), "1.16.5_HD_U_G8");
+        assert_eq!(item.record(), &record);
+        assert_eq!(item.size(), 3);
+        item.restore_to(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"jar");
+        assert!(get(&id).unwrap().is_none());
+        assert!(items().unwrap().is_empty());
+    }
+
+    #[test]
+    fn same_name_twice_and_remove() {
+        let _home = home::TestHome::new("trash-remove");
+        let record = VersionRecord::at(Utc::now());
+        let first = put("custom", &jar("custom", b"first"), &record).unwrap();
+        let second = put("custom", &jar("custom", b"second"), &record).unwrap();
+        assert_ne!(first, second);
+        // oldest first
+        let sizes: Vec<u64> = items().unwrap().iter().map(|item| item.size()).collect();
+        assert_eq!(sizes, [5, 6]);
+
+        get(&first).unwrap().unwrap().remove().unwrap();
+        assert!(get(&first).unwrap().is_none());
+        assert_eq!(items().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn expire_by_removed_time() {
+        let _home = home::TestHome::new("trash-expire");
+        let record = VersionRecord::at(Utc::now());
+        put("1.16.5_HD_U_G8", &jar("1.16.5_HD_U_G8", b"jar"), &record).unwrap();
+        assert!(expired(Utc::now() - Duration::days(1)).unwrap().is_empty());
+        let expired = expired(Utc::now() + Duration::seconds(1)).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].name(), "1.16.5_HD_U_G8");
+    }
+}
@@ -0,0 +1,151 @@
+use super::ParseVersionError;
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// A Minecraft release version like `1.8.9`, `1.20` or `1.21.4`.
+///
+/// An omitted patch number equals to 0, so `1.8` and `1.8.0` are the same version,
+/// but the original text is kept for display.
+#[derive(Debug, Clone)]
+pub struct MinecraftVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+    raw: String,
+}
+
+impl MinecraftVersion {
+    /// The version line this version belongs to, e.g. `(1, 16)` for `1.16.5`
+    pub fn group(&self) -> (u32, u32) {
+        (self.major, self.minor)
+    }
+    /// The version line as text, e.g. `1.16` for `1.16.5`
+    pub fn group_name(&self) -> String {
+        format!("{}.{}", self.major, self.minor)
+    }
+    /// The Minecraft version of an Optifine version name, e.g. `1.16.5` of `1.16.5_HD_U_G8`
+    pub fn of_opt_ver(opt_ver: &str) -> Option<Self> {
+        let (mc_ver, _) = opt_ver.split_once('_')?;
+        mc_ver.parse().ok()
+    }
+    fn key(&self) -> (u32, u32, u32) {
+        (self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for MinecraftVersion {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError(s.to_string());
+        let parts = s
+            .split('.')
+            .map(|part| match part.bytes().all(|b| b.is_ascii_digit()) {
+                true => part.parse::<u32>().map_err(|_| err()),
+                false => Err(err()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match parts[..] {
+            [major, minor] => Ok(Self {
+                major,
+                minor,
+                patch: 0,
+                raw: s.to_string(),
+            }),
+            [major, minor, patch] => Ok(Self {
+                major,
+                minor,
+                patch,
+                raw: s.to_string(),
+            }),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl fmt::Display for MinecraftVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl PartialEq for MinecraftVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for MinecraftVersion {}
+
+impl Hash for MinecraftVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for MinecraftVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MinecraftVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ver(s: &str) -> MinecraftVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for s in ["1.8.9", "1.20", "1.21.4", "1.8.0"] {
+            assert_eq!(ver(s).to_string(), s);
+        }
+        for s in [
+            "",
+            "1",
+            "1.",
+            "1.2.3.4",
+            "1.x",
+            "a.b",
+            "1.16.5_HD_U_G8",
+            "+1.2",
+        ] {
+            assert!(
+                s.parse::<MinecraftVersion>().is_err(),
+                "{s} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn ordering_is_numeric() {
+        assert!(ver("1.8.9") < ver("1.20"));
+        assert!(ver("1.20") < ver("1.20.1"));
+        assert!(ver("1.9") < ver("1.10"));
+        assert!(ver("1.21.4") < ver("1.21.10"));
+        assert_eq!(ver("1.8"), ver("1.8.0"));
+    }
+
+    #[test]
+    fn grouping() {
+        assert_eq!(ver("1.16.5").group(), (1, 16));
+        assert_eq!(ver("1.16").group_name(), "1.16");
+        assert_eq!(
+            MinecraftVersion::of_opt_ver("1.20.4_HD_U_I7_pre3"),
+            Some(ver("1.20.4"))
+        );
+        assert_eq!(MinecraftVersion::of_opt_ver("custom"), None);
+    }
+}
@@ -0,0 +1,14 @@
+mod minecraft;
+mod optifine;
+
+pub use minecraft::MinecraftVersion;
+pub use optifine::OptifineVersion;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVersionError(String);
+
+impl std::fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid version '{}'", self.0)
+    }
+}
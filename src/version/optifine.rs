@@ -0,0 +1,176 @@
+use super::{MinecraftVersion, ParseVersionError};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// An Optifine version like `1.16.5_HD_U_G8` or `1.20.4_HD_U_I7_pre3`.
+///
+/// The name is made of the Minecraft version, the edition (`HD_U`, `HD`, `L`, ...),
+/// the release letter and number, and an optional preview tag.
+/// Versions are ordered by Minecraft version, then release (`G8` < `H1`),
+/// and a preview goes before the release it previews (`I7_pre3` < `I7`).
+#[derive(Debug, Clone)]
+pub struct OptifineVersion {
+    mc_ver: MinecraftVersion,
+    edition: String,
+    letter: char,
+    number: u32,
+    preview: Option<u32>,
+    raw: String,
+}
+
+impl OptifineVersion {
+    pub fn is_preview(&self) -> bool {
+        self.preview.is_some()
+    }
+    fn key(&self) -> (&MinecraftVersion, char, u32, bool, Option<u32>, &str) {
+        // a release sorts after all of its previews
+        let is_release = self.preview.is_none();
+        (
+            &self.mc_ver,
+            self.letter,
+            self.number,
+            is_release,
+            self.preview,
+            &self.edition,
+        )
+    }
+}
+
+impl FromStr for OptifineVersion {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError(s.to_string());
+        let mut parts: Vec<&str> = s.split('_').collect();
+        let preview = match parts.last().and_then(|last| last.strip_prefix("pre")) {
+            Some(n) => {
+                let n = n.parse::<u32>().map_err(|_| err())?;
+                parts.pop();
+                Some(n)
+            }
+            None => None,
+        };
+        // at least mc version, edition and release
+        if parts.len() < 3 {
+            return Err(err());
+        }
+        let mc_ver = parts[0].parse::<MinecraftVersion>().map_err(|_| err())?;
+        let release = parts[parts.len() - 1];
+        let mut release_chars = release.chars();
+        let letter = release_chars
+            .next()
+            .filter(|c| c.is_ascii_uppercase())
+            .ok_or_else(err)?;
+        let number = match release_chars.as_str() {
+            "" => 0,
+            n if n.bytes().all(|b| b.is_ascii_digit()) => n.parse().map_err(|_| err())?,
+            _ => return Err(err()),
+        };
+        let edition = parts[1..parts.len() - 1].join("_");
+        if !edition
+            .bytes()
+            .all(|b| b.is_ascii_alphabetic() || b == b'_')
+        {
+            return Err(err());
+        }
+        Ok(Self {
+            mc_ver,
+            edition,
+            letter,
+            number,
+            preview,
+            raw: s.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for OptifineVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl PartialEq for OptifineVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for OptifineVersion {}
+
+impl Hash for OptifineVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for OptifineVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OptifineVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ver(s: &str) -> OptifineVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_parts() {
+        let v = ver("1.20.4_HD_U_I7_pre3");
+        assert_eq!(v.mc_ver, "1.20.4".parse().unwrap());
+        assert_eq!(v.edition, "HD_U");
+        assert_eq!((v.letter, v.number, v.preview), ('I', 7, Some(3)));
+        assert!(v.is_preview());
+        assert_eq!(v.to_string(), "1.20.4_HD_U_I7_pre3");
+
+        let v = ver("1.7.10_L_E7");
+        assert_eq!((v.edition.as_str(), v.letter, v.number), ("L", 'E', 7));
+        assert!(!v.is_preview());
+
+        for s in [
+            "",
+            "foo",
+            "1.16.5",
+            "1.16.5_G8",
+            "1.16.5_HD_U_g8",
+            "1.16.5_HD_U_G8_preX",
+            "x_HD_U_G8",
+        ] {
+            assert!(
+                s.parse::<OptifineVersion>().is_err(),
+                "{s} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let sorted = [
+            "1.8.9_HD_U_M5",
+            "1.16.5_HD_U_G7",
+            "1.16.5_HD_U_G8",
+            "1.16.5_HD_U_H1_pre2",
+            "1.16.5_HD_U_H1_pre10",
+            "1.16.5_HD_U_H1",
+            "1.20.4_HD_U_I7_pre3",
+            "1.20.4_HD_U_I7",
+        ];
+        for pair in sorted.windows(2) {
+            assert!(ver(pair[0]) < ver(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+    }
+}